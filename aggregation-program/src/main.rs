@@ -0,0 +1,25 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use alloy_sol_types::SolType;
+use checkpoint_proof::{aggregation::aggregate, checkpoint::AggregateCommitStruct};
+use sha2::{Digest, Sha256};
+
+pub fn main() {
+    let checkpoint_vkey = sp1_zkvm::io::read::<[u32; 8]>();
+    let public_values = sp1_zkvm::io::read::<Vec<Vec<u8>>>();
+
+    // Verify all the checkpoint proofs. The proofs themselves are passed to the prover
+    // separately and are verified using the vkey and digest of their public values.
+    for bytes in public_values.iter() {
+        let public_values_digest = Sha256::digest(bytes);
+        sp1_zkvm::lib::verify::verify_sp1_proof(&checkpoint_vkey, &public_values_digest.into());
+    }
+
+    let commit = aggregate(&checkpoint_vkey, &public_values);
+
+    // Encoded as params (instead of a single tuple) to match `abi.encode` in the verifier as
+    // the root hashes are dynamic
+    let bytes = AggregateCommitStruct::abi_encode_params(&commit);
+    sp1_zkvm::io::commit_slice(&bytes);
+}
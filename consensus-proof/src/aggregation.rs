@@ -0,0 +1,154 @@
+use crate::checkpoint::{AggregateCommitStruct, CommitStruct};
+
+use alloy_primitives::{Uint, B256};
+use alloy_sol_types::SolType;
+
+/// Converts the vkey digest (as used for verifying proofs in the zkVM) into bytes32 by
+/// concatenating the big endian representation of it's words.
+pub fn vkey_digest_to_bytes32(vkey: &[u32; 8]) -> B256 {
+    let mut bytes = [0u8; 32];
+    for (i, word) in vkey.iter().enumerate() {
+        bytes[i * 4..(i + 1) * 4].copy_from_slice(&word.to_be_bytes());
+    }
+    B256::from(bytes)
+}
+
+/// Aggregates the public values of a sequence of checkpoint proofs (which are assumed to be
+/// verified already) into a single commit, including the root hashes of all checkpoints. The
+/// checkpoints should be contiguous and all proofs should be anchored to the same L1 block and
+/// hence the same validator set (of the same network).
+pub fn aggregate(checkpoint_vkey: &[u32; 8], public_values: &[Vec<u8>]) -> AggregateCommitStruct {
+    assert!(
+        !public_values.is_empty(),
+        "no checkpoint proofs to aggregate"
+    );
+
+    let commits: Vec<CommitStruct> = public_values
+        .iter()
+        .map(|bytes| {
+            CommitStruct::abi_decode(bytes, true).expect("failed to decode checkpoint commit")
+        })
+        .collect();
+
+    let first = &commits[0];
+    for window in commits.windows(2) {
+        let (prev, next) = (&window[0], &window[1]);
+        assert_eq!(
+            next.bor_start_block,
+            prev.bor_block_number + Uint::from(1),
+            "checkpoints are not contiguous"
+        );
+        assert_eq!(
+            (next.prev_bor_block_number, next.prev_bor_block_hash),
+            (prev.bor_block_number, prev.bor_block_hash),
+            "checkpoints are not chained"
+        );
+        assert_eq!(
+            next.l1_block_hash, first.l1_block_hash,
+            "checkpoints anchored to different l1 blocks"
+        );
+        assert_eq!(
+            next.validator_set_hash, first.validator_set_hash,
+            "checkpoints verified against different validator sets"
+        );
+        assert_eq!(
+            (next.bor_chain_id, next.stake_manager),
+            (first.bor_chain_id, first.stake_manager),
+            "checkpoints of different networks"
+        );
+    }
+
+    let last = &commits[commits.len() - 1];
+    AggregateCommitStruct {
+        checkpoint_vkey: vkey_digest_to_bytes32(checkpoint_vkey),
+        l1_block_hash: first.l1_block_hash,
+        l1_block_number: first.l1_block_number,
+        prev_bor_block_hash: first.prev_bor_block_hash,
+        prev_bor_block_number: first.prev_bor_block_number,
+        bor_block_hash: last.bor_block_hash,
+        bor_start_block: first.bor_start_block,
+        bor_block_number: last.bor_block_number,
+        root_hashes: commits.iter().map(|commit| commit.root_hash).collect(),
+        validator_set_hash: first.validator_set_hash,
+        bor_chain_id: first.bor_chain_id,
+        stake_manager: first.stake_manager,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::Address;
+
+    fn commit(start: u64, end: u64, l1_block_hash: B256) -> Vec<u8> {
+        CommitStruct::abi_encode(&CommitStruct {
+            l1_block_hash,
+            l1_block_number: Uint::from(100),
+            prev_bor_block_hash: B256::with_last_byte((start - 1) as u8),
+            prev_bor_block_number: Uint::from(start - 1),
+            bor_block_hash: B256::with_last_byte(end as u8),
+            bor_start_block: Uint::from(start),
+            bor_block_number: Uint::from(end),
+            root_hash: B256::with_last_byte(end as u8 + 100),
+            validator_set_hash: B256::with_last_byte(1),
+            bor_chain_id: Uint::from(80002),
+            stake_manager: Address::with_last_byte(1),
+        })
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let vkey = [1u32, 2, 3, 4, 5, 6, 7, 8];
+        let l1_block_hash = B256::with_last_byte(42);
+        let aggregated = aggregate(
+            &vkey,
+            &[
+                commit(1, 10, l1_block_hash),
+                commit(11, 20, l1_block_hash),
+                commit(21, 30, l1_block_hash),
+            ],
+        );
+        assert_eq!(aggregated.prev_bor_block_number, Uint::from(0));
+        assert_eq!(aggregated.bor_start_block, Uint::from(1));
+        assert_eq!(aggregated.bor_block_number, Uint::from(30));
+        assert_eq!(aggregated.bor_block_hash, B256::with_last_byte(30));
+        assert_eq!(
+            aggregated.root_hashes,
+            vec![
+                B256::with_last_byte(110),
+                B256::with_last_byte(120),
+                B256::with_last_byte(130)
+            ]
+        );
+        assert_eq!(aggregated.l1_block_hash, l1_block_hash);
+        assert_eq!(aggregated.checkpoint_vkey[3], 1);
+        assert_eq!(aggregated.checkpoint_vkey[31], 8);
+        assert_eq!(aggregated.bor_chain_id, Uint::from(80002));
+        assert_eq!(aggregated.stake_manager, Address::with_last_byte(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "checkpoints are not contiguous")]
+    fn test_aggregate_gap() {
+        let l1_block_hash = B256::with_last_byte(42);
+        aggregate(
+            &[0u32; 8],
+            &[commit(1, 10, l1_block_hash), commit(12, 20, l1_block_hash)],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "checkpoints of different networks")]
+    fn test_aggregate_different_networks() {
+        let l1_block_hash = B256::with_last_byte(42);
+        let mut other = CommitStruct::abi_decode(&commit(11, 20, l1_block_hash), true).unwrap();
+        other.bor_chain_id = Uint::from(137);
+        aggregate(
+            &[0u32; 8],
+            &[
+                commit(1, 10, l1_block_hash),
+                CommitStruct::abi_encode(&other),
+            ],
+        );
+    }
+}
@@ -1,34 +1,90 @@
 use crate::{helper::*, types::checkpoint_to_bytes};
-use std::collections::HashMap;
+use std::collections::HashSet;
 
-use alloy_primitives::{address, keccak256, Address, FixedBytes, Uint, B256};
+use alloy_primitives::{address, keccak256, Address, Bytes, FixedBytes, Uint, B256};
 use alloy_sol_types::sol;
 use reth_primitives::Header;
+use zk_checkpoint_lib::stake_manager::AccountProof;
 
 pub const CALLER: Address = address!("0000000000000000000000000000000000000000");
 
 sol! {
-    contract RootChainInfo {
-        function getLastCheckpointEndBlock() external view returns (uint256);
-        function getActiveValidatorInfo() public view returns(address[] memory, uint256[] memory, uint256);
+    contract RootChain {
+        function getLastChildBlock() external view returns (uint256);
     }
 }
 
 sol! {
     struct CommitStruct {
         bytes32 l1_block_hash;
+        uint256 l1_block_number;
+        bytes32 prev_bor_block_hash;
+        uint256 prev_bor_block_number;
         bytes32 bor_block_hash;
+        uint256 bor_start_block;
         uint256 bor_block_number;
+        bytes32 root_hash;
+        bytes32 validator_set_hash;
+        uint256 bor_chain_id;
+        address stake_manager;
+    }
+}
+
+sol! {
+    struct AggregateCommitStruct {
+        bytes32 checkpoint_vkey;
+        bytes32 l1_block_hash;
+        uint256 l1_block_number;
+        bytes32 prev_bor_block_hash;
+        uint256 prev_bor_block_number;
+        bytes32 bor_block_hash;
+        uint256 bor_start_block;
+        uint256 bor_block_number;
+        bytes32[] root_hashes;
+        bytes32 validator_set_hash;
+        uint256 bor_chain_id;
+        address stake_manager;
     }
 }
 
 sol! {
     contract PoSVerifier {
+        event CheckpointVerified(uint256 indexed borBlockNumber, bytes32 borBlockHash, bytes32 validatorSetHash);
+
+        function verifier() external view returns (address);
+        function consensusProofVKey() external view returns (bytes32);
+        function aggregationProofVKey() external view returns (bytes32);
+        function consensusProofVKeyDigest() external view returns (bytes32);
+        function lastVerifiedBorBlockNumber() external view returns (uint256);
+        function lastVerifiedBorBlockHash() external view returns (bytes32);
+        function lastValidatorSetHash() external view returns (bytes32);
+        function verifiedRootHashes(bytes32) external view returns (bool);
+        function borChainId() external view returns (uint256);
+        function stakeManager() external view returns (address);
+
         function verifyCheckpointSignatures(
             bytes calldata _proofBytes,
             bytes32 _l1BlockHash,
+            uint256 _l1BlockNumber,
+            bytes32 _prevBorBlockHash,
+            uint256 _prevBorBlockNumber,
+            bytes32 _borBlockHash,
+            uint256 _borStartBlock,
+            uint256 _borBlockNumber,
+            bytes32 _rootHash,
+            bytes32 _validatorSetHash
+        ) public;
+        function verifyAggregatedCheckpoints(
+            bytes calldata _proofBytes,
+            bytes32 _l1BlockHash,
+            uint256 _l1BlockNumber,
+            bytes32 _prevBorBlockHash,
+            uint256 _prevBorBlockNumber,
             bytes32 _borBlockHash,
-            uint256 _borBlockNumber
+            uint256 _borStartBlock,
+            uint256 _borBlockNumber,
+            bytes32[] calldata _rootHashes,
+            bytes32 _validatorSetHash
         ) public;
     }
 }
@@ -64,46 +120,91 @@ pub struct CheckpointProofInput {
     pub tx_hash: B256,
     pub sigs: Vec<String>,
     pub signers: Vec<Address>,
+    pub validator_ids: Vec<u64>,
     pub state_sketch_bytes: Vec<u8>,
-    pub root_chain_info_address: Address,
+    pub root_chain_address: Address,
+    pub stake_manager_address: Address,
+    pub stake_manager_proof: AccountProof,
     pub l1_block_hash: B256,
-    pub bor_block_hash: B256,
+    // rlp encoded headers of the first and last bor block of the checkpoint along with the
+    // branches of their leaves in the checkpoint root hash
+    pub bor_start_header: Bytes,
+    pub bor_start_branch: Vec<B256>,
+    pub bor_end_header: Bytes,
+    pub bor_end_branch: Vec<B256>,
+    pub bor_chain_id: u64,
 }
 
 #[derive(Debug, Clone)]
 pub struct CheckpointProofCommit {
     pub l1_block_hash: B256,
+    pub l1_block_number: u64,
+    pub prev_bor_block_hash: B256,
+    pub prev_bor_block_number: u64,
     pub bor_block_hash: B256,
+    pub bor_start_block: u64,
     pub bor_block_number: u64,
+    pub root_hash: B256,
+    pub validator_set_hash: B256,
+    pub bor_chain_id: u64,
+    pub stake_manager: Address,
 }
 
 pub fn prove(input: CheckpointProofInput) -> CheckpointProofCommit {
     // 1. validate tx: hash(tx_data) == tx_hash
     let checkpoint = validate_checkpoint_msg(&input.tx_data, &input.tx_hash);
 
+    // Check if the checkpoint is for the bor chain id committed below, which the verifier
+    // checks against it's own so that a proof of a checkpoint from another network (e.g.
+    // amoy) can't be used in place of mainnet
+    assert_eq!(
+        checkpoint.bor_chain_id,
+        input.bor_chain_id.to_string(),
+        "checkpoint bor chain id mismatch"
+    );
+
     // 2. checkpoint.start_block = last_checkpoint_end_block + 1
     // SKIPPING this for testing old checkpoints
     // validate_checkpoint(
     //     checkpoint.start_block,
-    //     input.root_chain_info_address,
+    //     input.root_chain_address,
     //     input.state_sketch_bytes.clone(),
     // );
 
-    // 3. Check if we have same number of sigs and signers
+    // 3. Check the first and last bor block of the checkpoint against it's root hash so that
+    // the committed block hashes are the ones signed by the validators. The previous checkpoint
+    // ends at the parent of the first block, except for the first checkpoint which starts at
+    // the genesis block (whose parent hash is zero).
+    let root_hash = B256::from_slice(&checkpoint.root_hash);
+    let (start_header, _) = verify_checkpoint_header(
+        &input.bor_start_header,
+        &input.bor_start_branch,
+        checkpoint.start_block,
+        checkpoint.start_block,
+        root_hash,
+    );
+    let (_, bor_block_hash) = verify_checkpoint_header(
+        &input.bor_end_header,
+        &input.bor_end_branch,
+        checkpoint.end_block,
+        checkpoint.start_block,
+        root_hash,
+    );
+    let prev_bor_block_hash = start_header.parent_hash;
+    let prev_bor_block_number = checkpoint.start_block.saturating_sub(1);
+
+    // 4. Check if we have same number of sigs and signers
     assert_eq!(input.sigs.len(), input.signers.len());
 
-    // Fetch the validator info (addresses and stake) from the PoS contracts
-    let (signers, powers, total_power) = fetch_validator_info(
-        input.root_chain_info_address,
-        input.state_sketch_bytes.clone(),
+    // Fetch the active validator set (addresses and stake) from the stake manager storage
+    let l1_header = fetch_l1_header(&input.state_sketch_bytes, input.l1_block_hash);
+    let (validator_set, total_power) = fetch_validator_set(
+        &input.validator_ids,
+        input.stake_manager_address,
+        &input.stake_manager_proof,
+        l1_header.state_root,
     );
 
-    // Combine the data into a map of address -> total stake
-    let mut validator_stake_map = HashMap::new();
-    for (i, signer) in signers.iter().enumerate() {
-        validator_stake_map.insert(signer, powers[i]);
-    }
-
     // Initialise the majority stake
     let mut majority: Uint<256, 4> = Uint::from(0);
 
@@ -111,31 +212,40 @@ pub fn prove(input: CheckpointProofInput) -> CheckpointProofCommit {
     let mut message = vec![1]; // vote yes
     message.extend_from_slice(checkpoint_to_bytes(&checkpoint).as_slice());
 
-    // 4. Verify the signatures of all validators
+    // 5. Verify the signatures of all validators
     let mut verify_count = 0;
+    let mut seen_signers = HashSet::new();
     for (i, sig) in input.sigs.iter().enumerate() {
-        // check if it's a valid signer
-        assert!(validator_stake_map.contains_key(&input.signers[i]));
+        // check if it's a valid signer which hasn't been counted already
+        assert!(seen_signers.insert(input.signers[i]));
+        let validator = validator_set
+            .get_by_signer(&input.signers[i])
+            .expect("signer is not an active validator");
 
         // verify
         verify_signature(sig.as_str(), &keccak256(message.clone()), input.signers[i]);
 
         // increase the majority power
-        majority = majority.add_mod(validator_stake_map[&input.signers[i]], Uint::MAX);
+        majority = majority.add_mod(validator.stake, Uint::MAX);
         verify_count += 1;
     }
 
-    // 5. Check if majority >= 2/3 of total stake
-    let expected_majority = total_power
-        .mul_mod(Uint::from(2), Uint::MAX)
-        .div_ceil(Uint::from(3));
-    if majority <= expected_majority {
+    // 6. Check if majority > 2/3 of total stake
+    if !is_super_majority(majority, total_power) {
         panic!("Majority voting power is less than 2/3rd of the total power, total_power: {}, majority_power: {}, vc: {}", total_power, majority, verify_count);
     }
 
     CheckpointProofCommit {
-        bor_block_hash: input.bor_block_hash,
+        bor_block_hash,
         l1_block_hash: input.l1_block_hash,
+        l1_block_number: l1_header.number,
+        prev_bor_block_hash,
+        prev_bor_block_number,
+        bor_start_block: checkpoint.start_block,
         bor_block_number: checkpoint.end_block,
+        root_hash,
+        validator_set_hash: validator_set.hash(),
+        bor_chain_id: input.bor_chain_id,
+        stake_manager: input.stake_manager_address,
     }
 }
@@ -0,0 +1,244 @@
+use alloy_primitives::{keccak256, Address, Bloom, Bytes, B256, U256};
+use alloy_rlp::{Decodable, RlpDecodable, RlpEncodable};
+use alloy_trie::{
+    proof::{verify_proof, ProofRetainer},
+    HashBuilder, Nibbles,
+};
+use zk_checkpoint_lib::exit::ExitProof;
+
+#[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct ReceiptLog {
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+}
+
+/// Receipt as stored in the receipt trie (without the tx type).
+#[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct Receipt {
+    pub status: u64,
+    pub cumulative_gas_used: u64,
+    pub logs_bloom: Bloom,
+    pub logs: Vec<ReceiptLog>,
+}
+
+/// Encodes the receipt as stored in the receipt trie i.e. prefixed with the tx type for
+/// typed (non legacy) transactions.
+pub fn encode_receipt(tx_type: u8, receipt: &Receipt) -> Vec<u8> {
+    let mut buf = Vec::new();
+    if tx_type != 0 {
+        buf.push(tx_type);
+    }
+    buf.extend_from_slice(&alloy_rlp::encode(receipt));
+    buf
+}
+
+pub fn decode_receipt(bytes: &[u8]) -> Receipt {
+    let mut buf = match bytes.first() {
+        Some(tx_type) if *tx_type <= 0x7f => &bytes[1..],
+        _ => bytes,
+    };
+    Receipt::decode(&mut buf).expect("failed to decode receipt")
+}
+
+/// Leaf of a block in the checkpoint root hash i.e. keccak256 over the packed block number,
+/// timestamp, transactions root and receipts root.
+pub fn block_leaf(
+    number: u64,
+    timestamp: u64,
+    transactions_root: B256,
+    receipts_root: B256,
+) -> B256 {
+    let mut buf = Vec::with_capacity(128);
+    buf.extend_from_slice(&U256::from(number).to_be_bytes::<32>());
+    buf.extend_from_slice(&U256::from(timestamp).to_be_bytes::<32>());
+    buf.extend_from_slice(transactions_root.as_slice());
+    buf.extend_from_slice(receipts_root.as_slice());
+    keccak256(buf)
+}
+
+fn keccak256_pair(left: &B256, right: &B256) -> B256 {
+    let mut buf = [0u8; 64];
+    buf[..32].copy_from_slice(left.as_slice());
+    buf[32..].copy_from_slice(right.as_slice());
+    keccak256(buf)
+}
+
+/// Layers of the checkpoint merkle tree (from the leaves upwards) where the leaves are padded
+/// with zero hashes to the next power of two, same as done by bor for the checkpoint root.
+fn checkpoint_layers(leaves: &[B256]) -> Vec<Vec<B256>> {
+    assert!(!leaves.is_empty(), "no blocks in checkpoint");
+    let mut layer = leaves.to_vec();
+    layer.resize(leaves.len().next_power_of_two(), B256::ZERO);
+    let mut layers = vec![layer];
+    while layers[layers.len() - 1].len() > 1 {
+        let next = layers[layers.len() - 1]
+            .chunks(2)
+            .map(|pair| keccak256_pair(&pair[0], &pair[1]))
+            .collect();
+        layers.push(next);
+    }
+    layers
+}
+
+/// Root hash of the checkpoint with the given block leaves.
+pub fn checkpoint_root(leaves: &[B256]) -> B256 {
+    let layers = checkpoint_layers(leaves);
+    layers[layers.len() - 1][0]
+}
+
+/// Merkle branch (ordered from the leaf upwards) of the block leaf at `index`.
+pub fn checkpoint_branch(leaves: &[B256], index: usize) -> Vec<B256> {
+    let layers = checkpoint_layers(leaves);
+    (0..layers.len() - 1)
+        .map(|level| layers[level][(index >> level) ^ 1])
+        .collect()
+}
+
+/// Verifies the merkle branch of the block leaf at `index` against the checkpoint root hash.
+pub fn verify_checkpoint_branch(leaf: B256, index: u64, branch: &[B256], root: B256) -> bool {
+    let mut node = leaf;
+    for (i, sibling) in branch.iter().enumerate() {
+        node = if (index >> i) & 1 == 1 {
+            keccak256_pair(sibling, &node)
+        } else {
+            keccak256_pair(&node, sibling)
+        };
+    }
+    node == root
+}
+
+/// Builds the receipt trie from all the (encoded) receipts of a block and returns it's root
+/// along with the proof of the receipt at `index`.
+pub fn receipt_proof(receipts: &[Vec<u8>], index: u64) -> (B256, Vec<Bytes>) {
+    // Leaves need to be added in order of their keys i.e. rlp encoded indices
+    let mut leaves: Vec<(Nibbles, &Vec<u8>)> = receipts
+        .iter()
+        .enumerate()
+        .map(|(i, receipt)| (Nibbles::unpack(alloy_rlp::encode(i as u64)), receipt))
+        .collect();
+    leaves.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let target = Nibbles::unpack(alloy_rlp::encode(index));
+    let mut hash_builder =
+        HashBuilder::default().with_proof_retainer(ProofRetainer::new(vec![target]));
+    for (key, receipt) in leaves.iter() {
+        hash_builder.add_leaf(key.clone(), receipt);
+    }
+    let root = hash_builder.root();
+
+    // Proof nodes are keyed by their path, so sorting them orders them from the root
+    let mut proofs: Vec<(Nibbles, Bytes)> = hash_builder.take_proofs().into_iter().collect();
+    proofs.sort_by(|a, b| a.0.cmp(&b.0));
+    (root, proofs.into_iter().map(|(_, node)| node).collect())
+}
+
+/// Verifies the exit proof against the checkpoint root hash and returns the receipt along
+/// with the log at `log_index`.
+pub fn verify_exit_proof(proof: &ExitProof) -> (Receipt, ReceiptLog) {
+    assert!(
+        proof.start_block <= proof.block_number && proof.block_number <= proof.end_block,
+        "block {} not in checkpoint {}",
+        proof.block_number,
+        proof.checkpoint_id
+    );
+
+    // Verify the block against the checkpoint root hash
+    let leaf = block_leaf(
+        proof.block_number,
+        proof.timestamp,
+        proof.transactions_root,
+        proof.receipts_root,
+    );
+    assert!(
+        verify_checkpoint_branch(
+            leaf,
+            proof.block_number - proof.start_block,
+            &proof.block_proof,
+            proof.root_hash
+        ),
+        "block proof verification failed"
+    );
+
+    // Verify the receipt against the receipts root of the block
+    verify_proof(
+        proof.receipts_root,
+        Nibbles::unpack(alloy_rlp::encode(proof.tx_index)),
+        Some(proof.receipt.to_vec()),
+        &proof.receipt_proof,
+    )
+    .expect("receipt proof verification failed");
+
+    let receipt = decode_receipt(&proof.receipt);
+    let log = receipt
+        .logs
+        .get(proof.log_index as usize)
+        .cloned()
+        .unwrap_or_else(|| panic!("log {} not found in receipt", proof.log_index));
+    (receipt, log)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(logs: usize) -> Receipt {
+        Receipt {
+            status: 1,
+            cumulative_gas_used: 21000,
+            logs_bloom: Bloom::default(),
+            logs: (0..logs)
+                .map(|i| ReceiptLog {
+                    address: Address::with_last_byte(i as u8),
+                    topics: vec![B256::with_last_byte(i as u8)],
+                    data: Bytes::from(vec![i as u8; 4]),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_verify_exit_proof() {
+        let receipts: Vec<Vec<u8>> = (0..130)
+            .map(|i| encode_receipt((i % 3) as u8, &receipt(i % 4)))
+            .collect();
+        let tx_index = 129;
+        let (receipts_root, receipt_proof) = receipt_proof(&receipts, tx_index);
+
+        let (start_block, end_block, block_number) = (100u64, 110u64, 105u64);
+        let leaves: Vec<B256> = (start_block..=end_block)
+            .map(|number| {
+                let root = if number == block_number {
+                    receipts_root
+                } else {
+                    B256::ZERO
+                };
+                block_leaf(number, number * 2, B256::ZERO, root)
+            })
+            .collect();
+        let index = (block_number - start_block) as usize;
+
+        let proof = ExitProof {
+            checkpoint_id: 1,
+            root_hash: checkpoint_root(&leaves),
+            start_block,
+            end_block,
+            block_number,
+            timestamp: block_number * 2,
+            transactions_root: B256::ZERO,
+            receipts_root,
+            block_proof: checkpoint_branch(&leaves, index),
+            tx_index,
+            receipt: Bytes::from(receipts[tx_index as usize].clone()),
+            receipt_proof,
+            log_index: 0,
+        };
+        let (decoded, log) = verify_exit_proof(&proof);
+        assert_eq!(decoded, receipt(1));
+        assert_eq!(log.address, Address::with_last_byte(0));
+
+        let mut invalid = proof.clone();
+        invalid.timestamp += 1;
+        assert!(std::panic::catch_unwind(|| verify_exit_proof(&invalid)).is_err());
+    }
+}
@@ -0,0 +1,263 @@
+use crate::span::verify_span;
+
+use alloy_primitives::{Address, Bytes, B256};
+use alloy_sol_types::sol;
+use reth_primitives::{recover_signer_unchecked, Header};
+use zk_checkpoint_lib::{span::Span, validator_set::ValidatorSet};
+
+/// Length of the seal (signature of the block producer) at the end of the bor header extra data.
+pub const EXTRA_SEAL_LENGTH: usize = 65;
+
+sol! {
+    struct HeaderChainCommitStruct {
+        bytes32 checkpoint_bor_block_hash;
+        uint256 checkpoint_bor_block_number;
+        uint256 span_id;
+        bytes32 span_hash;
+        bytes32 validator_set_hash;
+        bytes32 tip_bor_block_hash;
+        uint256 tip_bor_block_number;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HeaderChainProofInput {
+    // bor block (and it's hash) which was included in a verified checkpoint
+    pub checkpoint_bor_block_hash: B256,
+    pub checkpoint_bor_block_number: u64,
+
+    // span the headers belong to, the validator set it's producers are checked against and
+    // the signatures of the validators over it
+    pub span: Span,
+    pub validator_set: ValidatorSet,
+    pub span_sigs: Vec<String>,
+    pub span_signers: Vec<Address>,
+
+    // bor headers following the checkpoint block in order
+    pub headers: Vec<Header>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HeaderChainProofCommit {
+    pub checkpoint_bor_block_hash: B256,
+    pub checkpoint_bor_block_number: u64,
+    pub span_id: u64,
+    pub span_hash: B256,
+    pub validator_set_hash: B256,
+    pub tip_bor_block_hash: B256,
+    pub tip_bor_block_number: u64,
+}
+
+/// Hash signed by the block producer i.e. the hash of the header without the seal. As in bor's
+/// `encodeSigHeader`, it's the rlp encoding of the legacy fields followed by the base fee (set
+/// since the Jaipur fork), any later fields aren't signed.
+pub fn seal_hash(header: &Header) -> B256 {
+    assert!(
+        header.extra_data.len() >= EXTRA_SEAL_LENGTH,
+        "bor header extra data missing seal"
+    );
+    let mut header = header.clone();
+    let length = header.extra_data.len() - EXTRA_SEAL_LENGTH;
+    header.extra_data = Bytes::copy_from_slice(&header.extra_data[..length]);
+    header.withdrawals_root = None;
+    header.blob_gas_used = None;
+    header.excess_blob_gas = None;
+    header.parent_beacon_block_root = None;
+    header.requests_root = None;
+    header.hash_slow()
+}
+
+/// Recovers the block producer which sealed the header from the signature in the extra data.
+pub fn recover_seal_signer(header: &Header) -> Address {
+    let length = header.extra_data.len();
+    assert!(
+        length >= EXTRA_SEAL_LENGTH,
+        "bor header extra data missing seal"
+    );
+    let mut sig = [0u8; 65];
+    sig.copy_from_slice(&header.extra_data[length - EXTRA_SEAL_LENGTH..]);
+
+    let signer = recover_signer_unchecked(&sig, &seal_hash(header))
+        .expect("failed to recover bor block producer");
+    Address::from_slice(signer.as_slice())
+}
+
+pub fn prove(input: HeaderChainProofInput) -> HeaderChainProofCommit {
+    assert!(!input.headers.is_empty(), "no bor headers to verify");
+
+    // Verify the span signatures and it's block producers against the validator set
+    let span_hash = verify_span(
+        &input.span,
+        &input.validator_set,
+        &input.span_sigs,
+        &input.span_signers,
+    );
+
+    // Verify that the headers form a chain starting from the checkpoint block and that each
+    // of them was sealed by one of the block producers of the span
+    let mut parent_hash = input.checkpoint_bor_block_hash;
+    let mut parent_number = input.checkpoint_bor_block_number;
+    for header in input.headers.iter() {
+        assert_eq!(
+            (header.parent_hash, header.number),
+            (parent_hash, parent_number + 1),
+            "bor header chain is not linked"
+        );
+
+        assert!(
+            input.span.contains(header.number),
+            "bor header {} not in span {}",
+            header.number,
+            input.span.id
+        );
+
+        let signer = recover_seal_signer(header);
+        assert!(
+            input.span.is_producer(&signer),
+            "bor header {} not sealed by a block producer, signer: {}",
+            header.number,
+            signer
+        );
+
+        parent_hash = header.hash_slow();
+        parent_number = header.number;
+    }
+
+    HeaderChainProofCommit {
+        checkpoint_bor_block_hash: input.checkpoint_bor_block_hash,
+        checkpoint_bor_block_number: input.checkpoint_bor_block_number,
+        span_id: input.span.id,
+        span_hash,
+        validator_set_hash: input.validator_set.hash(),
+        tip_bor_block_hash: parent_hash,
+        tip_bor_block_number: parent_number,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::tests::{sign, validator_set};
+    use alloy_primitives::{keccak256, Bloom, B64, U256};
+    use alloy_rlp::Encodable;
+    use reth_primitives::sign_message;
+    use zk_checkpoint_lib::span::Producer;
+
+    /// Bor header (after Jaipur) sealed with the given key.
+    fn sealed_header(key: u8) -> Header {
+        let mut header = Header {
+            parent_hash: B256::with_last_byte(1),
+            ommers_hash: B256::with_last_byte(2),
+            beneficiary: Address::ZERO,
+            state_root: B256::with_last_byte(3),
+            transactions_root: B256::with_last_byte(4),
+            receipts_root: B256::with_last_byte(5),
+            logs_bloom: Bloom::with_last_byte(6),
+            difficulty: U256::from(7),
+            number: 8,
+            gas_limit: 30_000_000,
+            gas_used: 21_000,
+            timestamp: 1_700_000_000,
+            mix_hash: B256::ZERO,
+            nonce: 0,
+            base_fee_per_gas: Some(30_000_000_000),
+            // Vanity followed by the (empty) seal
+            extra_data: Bytes::from(vec![9u8; 32 + EXTRA_SEAL_LENGTH]),
+            ..Default::default()
+        };
+        let signature = sign_message(B256::repeat_byte(key), seal_hash(&header)).unwrap();
+        let mut extra_data = vec![9u8; 32];
+        extra_data.extend_from_slice(&signature.r.to_be_bytes::<32>());
+        extra_data.extend_from_slice(&signature.s.to_be_bytes::<32>());
+        extra_data.push(signature.odd_y_parity as u8);
+        header.extra_data = Bytes::from(extra_data);
+        header
+    }
+
+    #[test]
+    fn test_seal_hash() {
+        let mut header = sealed_header(1);
+
+        // Encoded as in bor's `encodeSigHeader`, i.e. the extra data without the seal and the
+        // base fee after the legacy fields
+        let extra_data = Bytes::copy_from_slice(&header.extra_data[..32]);
+        let fields: [&dyn Encodable; 16] = [
+            &header.parent_hash,
+            &header.ommers_hash,
+            &header.beneficiary,
+            &header.state_root,
+            &header.transactions_root,
+            &header.receipts_root,
+            &header.logs_bloom,
+            &header.difficulty,
+            &header.number,
+            &header.gas_limit,
+            &header.gas_used,
+            &header.timestamp,
+            &extra_data,
+            &header.mix_hash,
+            &B64::ZERO,
+            &30_000_000_000u64,
+        ];
+        let mut payload = Vec::new();
+        for field in fields {
+            field.encode(&mut payload);
+        }
+        let mut encoded = Vec::new();
+        alloy_rlp::Header {
+            list: true,
+            payload_length: payload.len(),
+        }
+        .encode(&mut encoded);
+        encoded.extend_from_slice(&payload);
+        assert_eq!(seal_hash(&header), keccak256(&encoded));
+
+        // Fields which bor doesn't sign are ignored
+        header.withdrawals_root = Some(B256::with_last_byte(10));
+        assert_eq!(seal_hash(&header), keccak256(&encoded));
+    }
+
+    #[test]
+    fn test_recover_seal_signer() {
+        let (_, signers) = validator_set();
+        let signer = recover_seal_signer(&sealed_header(1));
+        assert_eq!(signer, signers[0]);
+        assert_eq!(recover_seal_signer(&sealed_header(2)), signers[1]);
+
+        // The signer changes if any of the signed fields does
+        let mut header = sealed_header(1);
+        header.base_fee_per_gas = Some(1);
+        assert_ne!(recover_seal_signer(&header), signer);
+    }
+
+    #[test]
+    #[should_panic(expected = "bor header chain is not linked")]
+    fn test_header_chain_not_linked() {
+        let header = Header {
+            parent_hash: B256::with_last_byte(1),
+            number: 11,
+            ..Default::default()
+        };
+        let (validator_set, signers) = validator_set();
+        let span = Span {
+            id: 1,
+            start_block: 0,
+            end_block: 100,
+            producers: vec![Producer {
+                id: 1,
+                signer: signers[0],
+                power: 10,
+            }],
+        };
+        let (span_sigs, span_signers) = (1..=3).map(|key| sign(&span, key)).unzip();
+        prove(HeaderChainProofInput {
+            checkpoint_bor_block_hash: B256::with_last_byte(2),
+            checkpoint_bor_block_number: 10,
+            span,
+            validator_set,
+            span_sigs,
+            span_signers,
+            headers: vec![header],
+        });
+    }
+}
@@ -1,15 +1,23 @@
 use crate::{
-    checkpoint::{RootChainInfo, CALLER},
+    checkpoint::{RootChain, CALLER},
+    exit::{block_leaf, verify_checkpoint_branch},
+    storage::verify_account_proof,
     types::*,
 };
 
 use base64::{prelude::BASE64_STANDARD, Engine};
 use core::str;
 use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use zk_checkpoint_lib::{
+    stake_manager::*,
+    validator_set::{Validator, ValidatorSet},
+};
 
 use alloy_primitives::{Address, FixedBytes, Uint, B256};
+use alloy_rlp::Decodable;
 use alloy_sol_types::{sol, SolCall};
-use reth_primitives::recover_signer_unchecked;
+use reth_primitives::{recover_signer_unchecked, Header};
 use sp1_cc_client_executor::{io::EVMStateSketch, ClientExecutor, ContractInput};
 
 fn sha256(decoded_tx_data: &[u8]) -> FixedBytes<32> {
@@ -61,49 +69,124 @@ pub fn verify_signature(signature: &str, message_hash: &[u8; 32], expected_signe
     );
 }
 
-pub fn fetch_validator_info(
-    root_chain_info_address: Address,
-    state_sketch_bytes: Vec<u8>,
-) -> (Vec<Address>, Vec<Uint<256, 4>>, Uint<256, 4>) {
-    let state_sketch = bincode::deserialize::<EVMStateSketch>(&state_sketch_bytes).unwrap();
-    let executor = ClientExecutor::new(state_sketch).unwrap();
+/// Validates the L1 block hash against the state sketch and returns it's header.
+pub fn fetch_l1_header(state_sketch_bytes: &[u8], l1_block_hash: B256) -> Header {
+    let state_sketch = bincode::deserialize::<EVMStateSketch>(state_sketch_bytes).unwrap();
+    assert_eq!(
+        state_sketch.header.hash_slow(),
+        l1_block_hash,
+        "l1 block hash mismatch with state sketch"
+    );
+    state_sketch.header
+}
 
-    // Call `getActiveValidatorInfo` on respective L1
-    let call = RootChainInfo::getActiveValidatorInfoCall {};
-    let call_input = ContractInput {
-        contract_address: root_chain_info_address,
-        caller_address: CALLER,
-        calldata: call.clone(),
+/// Decodes the rlp encoded bor header and checks that it's the block with the given number in
+/// the checkpoint starting at `start_block` i.e. that it's leaf is included in the checkpoint
+/// root hash at it's position. Returns the header along with it's hash.
+pub fn verify_checkpoint_header(
+    rlp: &[u8],
+    branch: &[B256],
+    number: u64,
+    start_block: u64,
+    root_hash: B256,
+) -> (Header, B256) {
+    let header = Header::decode(&mut &rlp[..]).expect("failed to decode bor header");
+    assert_eq!(header.number, number, "bor header number mismatch");
+    let leaf = block_leaf(
+        header.number,
+        header.timestamp,
+        header.transactions_root,
+        header.receipts_root,
+    );
+    assert!(
+        verify_checkpoint_branch(leaf, number - start_block, branch, root_hash),
+        "bor block {} not included in the checkpoint root hash",
+        number
+    );
+
+    // Hashed from the decoded header so that trailing bytes in the encoding can't change it
+    let hash = header.hash_slow();
+    (header, hash)
+}
+
+/// Builds the active validator set from the `StakeManager` storage (against the L1 state root).
+/// `validator_ids` should contain all active validators, the totals are checked against the
+/// stake manager which catches any validator missing from it. Returns the set along with the
+/// total stake (in wei).
+pub fn fetch_validator_set(
+    validator_ids: &[u64],
+    stake_manager_address: Address,
+    stake_manager_proof: &AccountProof,
+    state_root: B256,
+) -> (ValidatorSet, Uint<256, 4>) {
+    let storage = verify_account_proof(state_root, stake_manager_address, stake_manager_proof);
+    let read = |slot: B256| -> Uint<256, 4> {
+        *storage
+            .get(&slot)
+            .unwrap_or_else(|| panic!("missing proof for stake manager slot: {}", slot))
     };
-    let output = executor.execute(call_input).unwrap();
-    let response =
-        RootChainInfo::getActiveValidatorInfoCall::abi_decode_returns(&output.contractOutput, true)
-            .unwrap();
 
-    (response._0, response._1, response._2)
+    let current_epoch = read(current_epoch_slot());
+    let mut seen = HashSet::new();
+    let mut validator_set = ValidatorSet::default();
+    for id in validator_ids.iter().copied() {
+        assert!(seen.insert(id), "duplicate validator id: {}", id);
+        let status = decode_status(read(validator_slot(id, VALIDATOR_STATUS_OFFSET)));
+        let deactivation_epoch = read(validator_slot(id, VALIDATOR_DEACTIVATION_EPOCH_OFFSET));
+        if !is_active_validator(status, deactivation_epoch, current_epoch) {
+            continue;
+        }
+
+        let signer = decode_signer(read(validator_slot(id, VALIDATOR_SIGNER_OFFSET)));
+        let amount = read(validator_slot(id, VALIDATOR_AMOUNT_OFFSET));
+        let delegated_amount = read(validator_slot(id, VALIDATOR_DELEGATED_AMOUNT_OFFSET));
+        validator_set.upsert(Validator {
+            id,
+            signer,
+            stake: amount + delegated_amount,
+        });
+    }
+
+    let total_stake = read(total_stake_slot());
+    assert_eq!(
+        validator_set.total_stake(),
+        total_stake,
+        "validator set stake mismatch with stake manager"
+    );
+    assert_eq!(
+        Uint::<256, 4>::from(validator_set.len()),
+        read(staker_count_slot()),
+        "validator set size mismatch with stake manager"
+    );
+
+    (validator_set, total_stake)
+}
+
+/// Checks if the signed stake is a super majority of the total stake. This mirrors the
+/// check done by the stake manager on L1 i.e. signed stake >= (total stake * 2 / 3) + 1.
+pub fn is_super_majority(majority: Uint<256, 4>, total_power: Uint<256, 4>) -> bool {
+    let expected_majority = total_power * Uint::from(2) / Uint::from(3);
+    majority > expected_majority
 }
 
 pub fn validate_checkpoint(
     start_block: u64,
-    root_chain_info_address: Address,
+    root_chain_address: Address,
     state_sketch_bytes: Vec<u8>,
 ) {
     let state_sketch = bincode::deserialize::<EVMStateSketch>(&state_sketch_bytes).unwrap();
     let executor = ClientExecutor::new(state_sketch).unwrap();
 
-    // Call `getLastCheckpointEndBlock` on respective L1
-    let call = RootChainInfo::getLastCheckpointEndBlockCall {};
+    // Call `getLastChildBlock` on respective L1
+    let call = RootChain::getLastChildBlockCall {};
     let call_input = ContractInput {
-        contract_address: root_chain_info_address,
+        contract_address: root_chain_address,
         caller_address: CALLER,
         calldata: call.clone(),
     };
     let output = executor.execute(call_input).unwrap();
-    let response = RootChainInfo::getLastCheckpointEndBlockCall::abi_decode_returns(
-        &output.contractOutput,
-        true,
-    )
-    .unwrap();
+    let response =
+        RootChain::getLastChildBlockCall::abi_decode_returns(&output.contractOutput, true).unwrap();
 
     let last_end = response._0;
     let last_end_u64: u64 = last_end
@@ -111,3 +194,32 @@ pub fn validate_checkpoint(
         .expect("failed to convert last_end value to u64");
     assert_eq!(start_block, last_end_u64 + 1);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::U256;
+
+    #[test]
+    fn test_is_super_majority() {
+        // 1e18 * 300 wei, 2/3rd of which is exactly 200 tokens
+        let total = U256::from(300_000_000_000_000_000_000u128);
+        let two_third = U256::from(200_000_000_000_000_000_000u128);
+        assert!(!is_super_majority(two_third, total));
+        assert!(is_super_majority(two_third + U256::from(1), total));
+
+        // Sub-token differences should be respected at the boundary
+        let total = U256::from(300_000_000_000_000_000_001u128);
+        assert!(!is_super_majority(two_third, total));
+        assert!(is_super_majority(two_third + U256::from(1), total));
+
+        // 201 of 301.5 tokens is exactly 2/3 and just short of the majority in wei, while
+        // dividing into whole tokens (201 of 301) would accept it
+        let token = U256::from(1_000_000_000_000_000_000u128);
+        let total = U256::from(301_500_000_000_000_000_000u128);
+        let signed = U256::from(201_000_000_000_000_000_000u128);
+        assert!(is_super_majority(signed / token, total / token));
+        assert!(!is_super_majority(signed, total));
+        assert!(is_super_majority(signed + U256::from(1), total));
+    }
+}
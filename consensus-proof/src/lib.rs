@@ -1,3 +1,8 @@
+pub mod aggregation;
 pub mod checkpoint;
+pub mod exit;
+pub mod header_chain;
 pub mod helper;
+pub mod span;
+pub mod storage;
 pub mod types;
@@ -0,0 +1,199 @@
+use crate::helper::{is_super_majority, verify_signature};
+use crate::types::pad_to_32_bytes;
+use std::collections::HashSet;
+
+use alloy_primitives::{keccak256, Address, Uint, B256};
+use zk_checkpoint_lib::{span::Span, validator_set::ValidatorSet};
+
+/// Bytes of the span signed by the validators (after the vote byte), with every field padded to
+/// 32 bytes as in `checkpoint_to_bytes`: the id and block range followed by the id, signer and
+/// power of each block producer.
+pub fn span_to_bytes(span: &Span) -> Vec<u8> {
+    let mut result = Vec::new();
+    result.extend_from_slice(pad_to_32_bytes(&span.id.to_be_bytes()).as_slice());
+    result.extend_from_slice(pad_to_32_bytes(&span.start_block.to_be_bytes()).as_slice());
+    result.extend_from_slice(pad_to_32_bytes(&span.end_block.to_be_bytes()).as_slice());
+    for producer in span.producers.iter() {
+        result.extend_from_slice(pad_to_32_bytes(&producer.id.to_be_bytes()).as_slice());
+        result.extend_from_slice(pad_to_32_bytes(producer.signer.as_slice()).as_slice());
+        result.extend_from_slice(pad_to_32_bytes(&producer.power.to_be_bytes()).as_slice());
+    }
+    result
+}
+
+/// Verifies the span against the validator set and returns it's hash. As for checkpoints, the
+/// span (including it's block producers) must be signed by validators holding more than 2/3 of
+/// the total stake, and each producer must be an active validator (with the same signer).
+pub fn verify_span(
+    span: &Span,
+    validator_set: &ValidatorSet,
+    sigs: &[String],
+    signers: &[Address],
+) -> B256 {
+    assert!(
+        span.start_block <= span.end_block,
+        "invalid span range, start: {}, end: {}",
+        span.start_block,
+        span.end_block
+    );
+    assert!(!span.producers.is_empty(), "span has no block producers");
+
+    let mut seen = HashSet::new();
+    for producer in span.producers.iter() {
+        assert!(
+            seen.insert(producer.id),
+            "duplicate block producer: {}",
+            producer.id
+        );
+        let validator = validator_set
+            .get_by_signer(&producer.signer)
+            .unwrap_or_else(|| panic!("block producer {} is not a validator", producer.id));
+        assert_eq!(
+            validator.id, producer.id,
+            "block producer signer mismatch with validator set"
+        );
+        assert!(
+            producer.power > 0,
+            "block producer {} has no power",
+            producer.id
+        );
+    }
+
+    // Verify the signatures of the validators over the span (vote yes)
+    assert_eq!(sigs.len(), signers.len());
+    let mut message = vec![1];
+    message.extend_from_slice(span_to_bytes(span).as_slice());
+    let message_hash = keccak256(&message);
+
+    let mut majority: Uint<256, 4> = Uint::from(0);
+    let mut seen_signers = HashSet::new();
+    for (sig, signer) in sigs.iter().zip(signers.iter()) {
+        assert!(
+            seen_signers.insert(*signer),
+            "duplicate span signer: {}",
+            signer
+        );
+        let validator = validator_set
+            .get_by_signer(signer)
+            .expect("span signer is not an active validator");
+        verify_signature(sig.as_str(), &message_hash, *signer);
+        majority = majority.add_mod(validator.stake, Uint::MAX);
+    }
+    let total_power = validator_set.total_stake();
+    if !is_super_majority(majority, total_power) {
+        panic!(
+            "span majority voting power is less than 2/3rd of the total power, total_power: {}, majority_power: {}",
+            total_power, majority
+        );
+    }
+
+    keccak256(span_to_bytes(span))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use alloy_primitives::U256;
+    use base64::{prelude::BASE64_STANDARD, Engine};
+    use reth_primitives::{recover_signer_unchecked, sign_message};
+    use zk_checkpoint_lib::{span::Producer, validator_set::Validator};
+
+    fn span(producers: &[(u64, Address)]) -> Span {
+        Span {
+            id: 1,
+            start_block: 256,
+            end_block: 6655,
+            producers: producers
+                .iter()
+                .map(|(id, signer)| Producer {
+                    id: *id,
+                    signer: *signer,
+                    power: 10,
+                })
+                .collect(),
+        }
+    }
+
+    /// Signs the span (vote yes) using the given key, returning the signature in the format
+    /// of heimdall along with the signer.
+    pub(crate) fn sign(span: &Span, key: u8) -> (String, Address) {
+        let mut message = vec![1];
+        message.extend_from_slice(span_to_bytes(span).as_slice());
+        let hash = keccak256(&message);
+        let signature = sign_message(B256::repeat_byte(key), hash).unwrap();
+        let mut sig = [0u8; 65];
+        sig[..32].copy_from_slice(&signature.r.to_be_bytes::<32>());
+        sig[32..64].copy_from_slice(&signature.s.to_be_bytes::<32>());
+        sig[64] = signature.odd_y_parity as u8;
+        let signer = recover_signer_unchecked(&sig, &hash).unwrap();
+        (
+            BASE64_STANDARD.encode(sig),
+            Address::from_slice(signer.as_slice()),
+        )
+    }
+
+    /// Validators 1 to 3 (with equal stake) along with the signers of their keys.
+    pub(crate) fn validator_set() -> (ValidatorSet, Vec<Address>) {
+        let signers: Vec<Address> = (1..=3u8).map(|key| sign(&Span::default(), key).1).collect();
+        let mut validator_set = ValidatorSet::default();
+        for (i, signer) in signers.iter().enumerate() {
+            validator_set.upsert(Validator {
+                id: i as u64 + 1,
+                signer: *signer,
+                stake: U256::from(10),
+            });
+        }
+        (validator_set, signers)
+    }
+
+    fn verify(span: &Span, validator_set: &ValidatorSet, votes: &[(String, Address)]) -> B256 {
+        let (sigs, signers): (Vec<String>, Vec<Address>) = votes.iter().cloned().unzip();
+        verify_span(span, validator_set, &sigs, &signers)
+    }
+
+    #[test]
+    fn test_verify_span() {
+        let (validator_set, signers) = validator_set();
+        let span = span(&[(1, signers[0]), (3, signers[2])]);
+        let votes: Vec<_> = (1..=3).map(|key| sign(&span, key)).collect();
+        assert_eq!(
+            verify(&span, &validator_set, &votes),
+            keccak256(span_to_bytes(&span))
+        );
+
+        // Producers which aren't validators (or with another signer)
+        let result = std::panic::catch_unwind(|| {
+            let span = self::span(&[(4, Address::with_last_byte(4))]);
+            let votes: Vec<_> = (1..=3).map(|key| sign(&span, key)).collect();
+            verify(&span, &validator_set, &votes)
+        });
+        assert!(result.is_err());
+        let result = std::panic::catch_unwind(|| {
+            let span = self::span(&[(1, signers[1])]);
+            let votes: Vec<_> = (1..=3).map(|key| sign(&span, key)).collect();
+            verify(&span, &validator_set, &votes)
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "span majority voting power is less than 2/3rd")]
+    fn test_verify_span_without_majority() {
+        let (validator_set, signers) = validator_set();
+        let span = span(&[(1, signers[0])]);
+        let votes: Vec<_> = (1..=2).map(|key| sign(&span, key)).collect();
+        verify(&span, &validator_set, &votes);
+    }
+
+    #[test]
+    #[should_panic(expected = "recovered and expected signature mismatch")]
+    fn test_verify_span_forged_producers() {
+        // The signatures are for a span produced by validator 1 only, which is replaced by
+        // validator 2 (still a valid producer)
+        let (validator_set, signers) = validator_set();
+        let votes: Vec<_> = (1..=3)
+            .map(|key| sign(&span(&[(1, signers[0])]), key))
+            .collect();
+        verify(&span(&[(2, signers[1])]), &validator_set, &votes);
+    }
+}
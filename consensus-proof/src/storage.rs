@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use alloy_primitives::{keccak256, Address, B256, U256};
+use alloy_rlp::RlpEncodable;
+use alloy_trie::{proof::verify_proof, Nibbles};
+use zk_checkpoint_lib::stake_manager::AccountProof;
+
+/// Account as stored in the state trie.
+#[derive(Debug, RlpEncodable)]
+struct TrieAccount {
+    nonce: u64,
+    balance: U256,
+    storage_root: B256,
+    code_hash: B256,
+}
+
+/// Verifies the account proof against the given state root followed by all the storage
+/// proofs against the account's storage root. Returns the verified slot values.
+pub fn verify_account_proof(
+    state_root: B256,
+    address: Address,
+    proof: &AccountProof,
+) -> HashMap<B256, U256> {
+    let account = TrieAccount {
+        nonce: proof.nonce,
+        balance: proof.balance,
+        storage_root: proof.storage_hash,
+        code_hash: proof.code_hash,
+    };
+    verify_proof(
+        state_root,
+        Nibbles::unpack(keccak256(address)),
+        Some(alloy_rlp::encode(&account)),
+        &proof.account_proof,
+    )
+    .expect("account proof verification failed");
+
+    let mut storage = HashMap::new();
+    for storage_proof in proof.storage_proofs.iter() {
+        // Zero values are not stored in the trie and are proven by exclusion
+        let expected_value = if storage_proof.value.is_zero() {
+            None
+        } else {
+            Some(alloy_rlp::encode(storage_proof.value))
+        };
+        verify_proof(
+            proof.storage_hash,
+            Nibbles::unpack(keccak256(storage_proof.key)),
+            expected_value,
+            &storage_proof.proof,
+        )
+        .expect("storage proof verification failed");
+        storage.insert(storage_proof.key, storage_proof.value);
+    }
+
+    storage
+}
@@ -24,29 +24,170 @@ interface ISP1VerifierWithHash is ISP1Verifier {
 }
 
 contract PoSVerifier {
+    // Owner allowed to migrate the verifier to a new program
+    address public owner;
+
     // SP1 related
     address public verifier;
     bytes32 public consensusProofVKey;
 
+    // Aggregation related. The aggregation program commits the vkey digest (as used for
+    // recursive verification) of the consensus proof program instead of `consensusProofVKey`.
+    bytes32 public aggregationProofVKey;
+    bytes32 public consensusProofVKeyDigest;
+
     // Last verified bor block details
     uint256 public lastVerifiedBorBlockNumber;
     bytes32 public lastVerifiedBorBlockHash;
 
-    constructor(address _verifier, bytes32 _consensusProofVKey) {
+    // Hash of the validator set used in the last verified proof
+    bytes32 public lastValidatorSetHash;
+
+    // Root hashes of all verified checkpoints, which exits (receipts of bor blocks up to the
+    // last verified block) are proven against
+    mapping(bytes32 => bool) public verifiedRootHashes;
+
+    // Network the proofs are expected to be for. Both are committed by the program so that
+    // a proof of another network (or against a different stake manager) is rejected.
+    uint256 public borChainId;
+    address public stakeManager;
+
+    event CheckpointVerified(
+        uint256 indexed borBlockNumber,
+        bytes32 borBlockHash,
+        bytes32 validatorSetHash
+    );
+
+    constructor(
+        address _verifier,
+        bytes32 _consensusProofVKey,
+        uint256 _borChainId,
+        address _stakeManager
+    ) {
+        owner = msg.sender;
         verifier = _verifier;
         consensusProofVKey = _consensusProofVKey;
+        borChainId = _borChainId;
+        stakeManager = _stakeManager;
+    }
+
+    // Points the verifier to a new version of the consensus proof program (e.g. when the
+    // program starts reading a different validator info ABI). The last verified bor block
+    // is retained so that proofs continue from where the previous program left off.
+    function updateConsensusProofVKey(bytes32 _consensusProofVKey) public {
+        require(msg.sender == owner, "PoSVerifier: caller is not the owner");
+        consensusProofVKey = _consensusProofVKey;
+    }
+
+    function updateAggregationProofVKeys(
+        bytes32 _aggregationProofVKey,
+        bytes32 _consensusProofVKeyDigest
+    ) public {
+        require(msg.sender == owner, "PoSVerifier: caller is not the owner");
+        aggregationProofVKey = _aggregationProofVKey;
+        consensusProofVKeyDigest = _consensusProofVKeyDigest;
     }
 
     function verifyCheckpointSignatures(
         bytes calldata _proofBytes,
         bytes32 _l1BlockHash,
+        uint256 _l1BlockNumber,
+        bytes32 _prevBorBlockHash,
+        uint256 _prevBorBlockNumber,
         bytes32 _borBlockHash,
-        uint256 _borBlockNumber
+        uint256 _borStartBlock,
+        uint256 _borBlockNumber,
+        bytes32 _rootHash,
+        bytes32 _validatorSetHash
     ) public {
-        bytes memory publicValues = abi.encodePacked(_l1BlockHash, _borBlockHash, _borBlockNumber);
+        _checkPrevState(_prevBorBlockHash, _prevBorBlockNumber);
+        bytes memory publicValues = abi.encode(
+            _l1BlockHash,
+            _l1BlockNumber,
+            _prevBorBlockHash,
+            _prevBorBlockNumber,
+            _borBlockHash,
+            _borStartBlock,
+            _borBlockNumber,
+            _rootHash,
+            _validatorSetHash,
+            borChainId,
+            stakeManager
+        );
         ISP1Verifier(verifier).verifyProof(consensusProofVKey, publicValues, _proofBytes);
+        verifiedRootHashes[_rootHash] = true;
+        _updateState(_borBlockHash, _borBlockNumber, _validatorSetHash);
+    }
+
+    // Verifies a single proof aggregating a contiguous sequence of checkpoint proofs from
+    // `_borStartBlock` to `_borBlockNumber`, along with the root hashes of all of them.
+    function verifyAggregatedCheckpoints(
+        bytes calldata _proofBytes,
+        bytes32 _l1BlockHash,
+        uint256 _l1BlockNumber,
+        bytes32 _prevBorBlockHash,
+        uint256 _prevBorBlockNumber,
+        bytes32 _borBlockHash,
+        uint256 _borStartBlock,
+        uint256 _borBlockNumber,
+        bytes32[] calldata _rootHashes,
+        bytes32 _validatorSetHash
+    ) public {
+        require(aggregationProofVKey != bytes32(0), "PoSVerifier: aggregation not enabled");
+        _checkPrevState(_prevBorBlockHash, _prevBorBlockNumber);
+        // Same as `abi.encode` of all the values (with the root hashes encoded after the 12
+        // head words), split up to stay within the stack limit
+        bytes memory publicValues = bytes.concat(
+            abi.encode(
+                consensusProofVKeyDigest,
+                _l1BlockHash,
+                _l1BlockNumber,
+                _prevBorBlockHash,
+                _prevBorBlockNumber,
+                _borBlockHash,
+                _borStartBlock,
+                _borBlockNumber
+            ),
+            abi.encode(
+                uint256(12 * 32),
+                _validatorSetHash,
+                borChainId,
+                stakeManager,
+                _rootHashes.length
+            ),
+            abi.encodePacked(_rootHashes)
+        );
+        ISP1Verifier(verifier).verifyProof(aggregationProofVKey, publicValues, _proofBytes);
+        for (uint256 i = 0; i < _rootHashes.length; i++) {
+            verifiedRootHashes[_rootHashes[i]] = true;
+        }
+        _updateState(_borBlockHash, _borBlockNumber, _validatorSetHash);
+    }
+
+    // Proofs must continue from the last verified checkpoint (unless it's the first one) so
+    // that out of order or gap submissions are rejected.
+    function _checkPrevState(
+        bytes32 _prevBorBlockHash,
+        uint256 _prevBorBlockNumber
+    ) internal view {
+        if (lastVerifiedBorBlockNumber != 0) {
+            require(
+                _prevBorBlockNumber == lastVerifiedBorBlockNumber &&
+                    _prevBorBlockHash == lastVerifiedBorBlockHash,
+                "PoSVerifier: previous checkpoint mismatch"
+            );
+        }
+    }
+
+    function _updateState(
+        bytes32 _borBlockHash,
+        uint256 _borBlockNumber,
+        bytes32 _validatorSetHash
+    ) internal {
         lastVerifiedBorBlockHash = _borBlockHash;
         lastVerifiedBorBlockNumber = _borBlockNumber;
+        lastValidatorSetHash = _validatorSetHash;
+        emit CheckpointVerified(_borBlockNumber, _borBlockHash, _validatorSetHash);
     }
 
 }
\ No newline at end of file
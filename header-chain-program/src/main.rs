@@ -0,0 +1,39 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use alloy_primitives::{Address, Uint, B256};
+use alloy_sol_types::SolType;
+use checkpoint_proof::header_chain::{prove, HeaderChainCommitStruct, HeaderChainProofInput};
+use reth_primitives::Header;
+use zk_checkpoint_lib::{span::Span, validator_set::ValidatorSet};
+
+pub fn main() {
+    let checkpoint_bor_block_hash = sp1_zkvm::io::read::<B256>();
+    let checkpoint_bor_block_number = sp1_zkvm::io::read::<u64>();
+    let span = sp1_zkvm::io::read::<Span>();
+    let validator_set = sp1_zkvm::io::read::<ValidatorSet>();
+    let span_sigs = sp1_zkvm::io::read::<Vec<String>>();
+    let span_signers = sp1_zkvm::io::read::<Vec<Address>>();
+    let headers = sp1_zkvm::io::read::<Vec<Header>>();
+    let input = HeaderChainProofInput {
+        checkpoint_bor_block_hash,
+        checkpoint_bor_block_number,
+        span,
+        validator_set,
+        span_sigs,
+        span_signers,
+        headers,
+    };
+    let commit = prove(input);
+
+    let bytes = HeaderChainCommitStruct::abi_encode_packed(&HeaderChainCommitStruct {
+        checkpoint_bor_block_hash: commit.checkpoint_bor_block_hash,
+        checkpoint_bor_block_number: Uint::from(commit.checkpoint_bor_block_number),
+        span_id: Uint::from(commit.span_id),
+        span_hash: commit.span_hash,
+        validator_set_hash: commit.validator_set_hash,
+        tip_bor_block_hash: commit.tip_bor_block_hash,
+        tip_bor_block_number: Uint::from(commit.tip_bor_block_number),
+    });
+    sp1_zkvm::io::commit_slice(&bytes);
+}
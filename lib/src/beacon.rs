@@ -0,0 +1,119 @@
+use alloy_primitives::{address, Address, B256};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// EIP-4788 contract holding the beacon block roots on L1.
+pub const BEACON_ROOTS_ADDRESS: Address = address!("000F3df6D732807Ef1319fB7B8bB8522d0Beac02");
+
+/// Number of beacon roots (keyed by timestamp) kept by the EIP-4788 contract.
+pub const BEACON_ROOTS_HISTORY_LENGTH: u64 = 8191;
+
+/// Number of recent blocks for which `blockhash` is available in the EVM.
+pub const BLOCKHASH_HISTORY_LENGTH: u64 = 256;
+
+/// Generalized index of `block_hash` of the execution payload in a beacon block i.e.
+/// `BeaconBlock.body (12)` -> `BeaconBlockBody.execution_payload (25)` ->
+/// `ExecutionPayload.block_hash (44)`.
+pub const EXECUTION_BLOCK_HASH_GINDEX: u64 = 6444;
+
+/// Proof of an L1 execution block hash against a beacon block root which can be looked up
+/// on L1 from the EIP-4788 contract using `timestamp` (of the next execution block).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BeaconRootProof {
+    pub timestamp: u64,
+    pub beacon_root: B256,
+    pub branch: Vec<B256>,
+}
+
+impl BeaconRootProof {
+    /// Verifies the given execution block hash against the beacon root.
+    pub fn verify(&self, block_hash: B256) -> bool {
+        verify_merkle_branch(
+            block_hash,
+            &self.branch,
+            EXECUTION_BLOCK_HASH_GINDEX,
+            self.beacon_root,
+        )
+    }
+}
+
+pub fn sha256_pair(left: &B256, right: &B256) -> B256 {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    B256::from_slice(hasher.finalize().as_slice())
+}
+
+/// Verifies an SSZ merkle branch (ordered from the leaf upwards) for the given generalized
+/// index.
+pub fn verify_merkle_branch(leaf: B256, branch: &[B256], gindex: u64, root: B256) -> bool {
+    if gindex >> branch.len() != 1 {
+        return false;
+    }
+    let mut node = leaf;
+    for (i, sibling) in branch.iter().enumerate() {
+        node = if (gindex >> i) & 1 == 1 {
+            sha256_pair(sibling, &node)
+        } else {
+            sha256_pair(&node, sibling)
+        };
+    }
+    node == root
+}
+
+/// Merkleizes the chunks into a tree of the given depth (padding with zero chunks) and
+/// returns all of it's layers from the leaves upwards.
+fn merkle_layers(chunks: &[B256], depth: usize) -> Vec<Vec<B256>> {
+    assert!(chunks.len() <= 1 << depth, "too many chunks for depth");
+    let mut layer = chunks.to_vec();
+    layer.resize(1 << depth, B256::ZERO);
+    let mut layers = vec![layer];
+    for _ in 0..depth {
+        let prev = &layers[layers.len() - 1];
+        let next = prev
+            .chunks(2)
+            .map(|pair| sha256_pair(&pair[0], &pair[1]))
+            .collect();
+        layers.push(next);
+    }
+    layers
+}
+
+/// SSZ hash tree root of the chunks merkleized into a tree of the given depth.
+pub fn merkle_root(chunks: &[B256], depth: usize) -> B256 {
+    merkle_layers(chunks, depth)[depth][0]
+}
+
+/// SSZ merkle branch (ordered from the leaf upwards) of the chunk at `index`.
+pub fn merkle_branch(chunks: &[B256], depth: usize, index: usize) -> Vec<B256> {
+    let layers = merkle_layers(chunks, depth);
+    (0..depth)
+        .map(|level| layers[level][(index >> level) ^ 1])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merkle_branch() {
+        let chunks: Vec<B256> = (0..5u8).map(B256::with_last_byte).collect();
+        let root = merkle_root(&chunks, 3);
+        for (index, chunk) in chunks.iter().enumerate() {
+            let branch = merkle_branch(&chunks, 3, index);
+            assert!(verify_merkle_branch(
+                *chunk,
+                &branch,
+                8 + index as u64,
+                root
+            ));
+            assert!(!verify_merkle_branch(
+                *chunk,
+                &branch,
+                9 + index as u64,
+                root
+            ));
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use alloy_primitives::{Bytes, B256};
+use serde::{Deserialize, Serialize};
+
+/// Proof of a bor receipt (and one of it's logs) being included in a checkpoint i.e. the
+/// inclusion of the block in the checkpoint root hash followed by the inclusion of the receipt
+/// in the block's receipt trie.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExitProof {
+    // checkpoint the block belongs to
+    pub checkpoint_id: u64,
+    pub root_hash: B256,
+    pub start_block: u64,
+    pub end_block: u64,
+
+    // block (as hashed into the checkpoint root) and it's merkle branch
+    pub block_number: u64,
+    pub timestamp: u64,
+    pub transactions_root: B256,
+    pub receipts_root: B256,
+    pub block_proof: Vec<B256>,
+
+    // receipt and it's proof against the receipts root
+    pub tx_index: u64,
+    pub receipt: Bytes,
+    pub receipt_proof: Vec<Bytes>,
+    pub log_index: u64,
+}
@@ -1,31 +1,123 @@
-use alloy_primitives::{address, Address, B256};
+use alloy_primitives::{address, Address, Bytes, B256};
 use alloy_sol_types::sol;
 use serde::{Deserialize, Serialize};
 
+pub mod beacon;
+pub mod exit;
+pub mod network;
+pub mod span;
+pub mod stake_manager;
+pub mod validator_set;
+
+use stake_manager::AccountProof;
+
 pub const CALLER: Address = address!("0000000000000000000000000000000000000000");
 
 sol! {
-    contract RootChainInfo {
-        function getLastCheckpointEndBlock() external view returns (uint256);
-        function getActiveValidatorInfo() public view returns(address[] memory, uint256[] memory, uint256);
+    contract RootChain {
+        function getLastChildBlock() external view returns (uint256);
+        function currentHeaderBlock() external view returns (uint256);
+        function headerBlocks(uint256) external view returns (bytes32 root, uint256 start, uint256 end, uint256 createdAt, address proposer);
+    }
+}
+
+sol! {
+    contract StakeManager {
+        function NFTCounter() external view returns (uint256);
+        function currentEpoch() external view returns (uint256);
+        function validators(uint256) external view returns (uint256, uint256, uint256, uint256, uint256, address, address, uint8, uint256, uint256, uint256, uint256, uint256);
+        function validatorState() external view returns (uint256, uint256);
     }
 }
 
 sol! {
     struct CommitStruct {
         bytes32 l1_block_hash;
+        uint256 l1_block_number;
+        bytes32 prev_bor_block_hash;
+        uint256 prev_bor_block_number;
+        bytes32 bor_block_hash;
+        uint256 bor_start_block;
+        uint256 bor_block_number;
+        bytes32 root_hash;
+        bytes32 validator_set_hash;
+        uint256 bor_chain_id;
+        address stake_manager;
+    }
+}
+
+sol! {
+    struct AggregateCommitStruct {
+        bytes32 checkpoint_vkey;
+        bytes32 l1_block_hash;
+        uint256 l1_block_number;
+        bytes32 prev_bor_block_hash;
+        uint256 prev_bor_block_number;
         bytes32 bor_block_hash;
+        uint256 bor_start_block;
         uint256 bor_block_number;
+        bytes32[] root_hashes;
+        bytes32 validator_set_hash;
+        uint256 bor_chain_id;
+        address stake_manager;
+    }
+}
+
+sol! {
+    struct HeaderChainCommitStruct {
+        bytes32 checkpoint_bor_block_hash;
+        uint256 checkpoint_bor_block_number;
+        uint256 span_id;
+        bytes32 span_hash;
+        bytes32 validator_set_hash;
+        bytes32 tip_bor_block_hash;
+        uint256 tip_bor_block_number;
     }
 }
 
 sol! {
     contract PoSVerifier {
+        event CheckpointVerified(uint256 indexed borBlockNumber, bytes32 borBlockHash, bytes32 validatorSetHash);
+
+        function verifier() external view returns (address);
+        function consensusProofVKey() external view returns (bytes32);
+        function aggregationProofVKey() external view returns (bytes32);
+        function consensusProofVKeyDigest() external view returns (bytes32);
+        function lastVerifiedBorBlockNumber() external view returns (uint256);
+        function lastVerifiedBorBlockHash() external view returns (bytes32);
+        function lastValidatorSetHash() external view returns (bytes32);
+        function verifiedRootHashes(bytes32) external view returns (bool);
+        function borChainId() external view returns (uint256);
+        function stakeManager() external view returns (address);
+
         function verifyCheckpointSignatures(
             bytes calldata _proofBytes,
             bytes32 _l1BlockHash,
+            uint256 _l1BlockNumber,
+            bytes32 _prevBorBlockHash,
+            uint256 _prevBorBlockNumber,
             bytes32 _borBlockHash,
-            uint256 _borBlockNumber
+            uint256 _borStartBlock,
+            uint256 _borBlockNumber,
+            bytes32 _rootHash,
+            bytes32 _validatorSetHash
+        ) public;
+        function verifyAggregatedCheckpoints(
+            bytes calldata _proofBytes,
+            bytes32 _l1BlockHash,
+            uint256 _l1BlockNumber,
+            bytes32 _prevBorBlockHash,
+            uint256 _prevBorBlockNumber,
+            bytes32 _borBlockHash,
+            uint256 _borStartBlock,
+            uint256 _borBlockNumber,
+            bytes32[] calldata _rootHashes,
+            bytes32 _validatorSetHash
+        ) public;
+        function updateConsensusProofVKey(bytes32 _consensusProofVKey) public;
+        function updateAggregationProofVKeys(
+            bytes32 _aggregationProofVKey,
+            bytes32 _consensusProofVKeyDigest
         ) public;
     }
 }
@@ -36,17 +128,38 @@ pub struct CheckpointProofInput {
     pub tx_hash: B256,
     pub sigs: Vec<String>,
     pub signers: Vec<Address>,
+    pub validator_ids: Vec<u64>,
     pub state_sketch_bytes: Vec<u8>,
-    pub root_chain_info_address: Address,
+    pub root_chain_address: Address,
+    pub stake_manager_address: Address,
+    pub stake_manager_proof: AccountProof,
     pub l1_block_hash: B256,
-    pub bor_block_hash: B256,
+    /// Rlp encoded headers of the first and last bor block of the checkpoint along with the
+    /// branches of their leaves in the checkpoint root hash.
+    pub bor_start_header: Bytes,
+    pub bor_start_branch: Vec<B256>,
+    pub bor_end_header: Bytes,
+    pub bor_end_branch: Vec<B256>,
+    pub bor_chain_id: u64,
+    /// Id of the checkpoint, only used by the operator (e.g. for metrics) as the program
+    /// doesn't read it. Missing in older inputs.
+    #[serde(default)]
+    pub checkpoint_id: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheckpointProofCommit {
     pub l1_block_hash: B256,
+    pub l1_block_number: u64,
+    pub prev_bor_block_hash: B256,
+    pub prev_bor_block_number: u64,
     pub bor_block_hash: B256,
+    pub bor_start_block: u64,
     pub bor_block_number: u64,
+    pub root_hash: B256,
+    pub validator_set_hash: B256,
+    pub bor_chain_id: u64,
+    pub stake_manager: Address,
 }
 
 #[derive(Debug, Deserialize)]
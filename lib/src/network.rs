@@ -0,0 +1,120 @@
+use alloy_primitives::{address, Address};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Polygon PoS networks with their bor chain id, the L1 they settle on and the L1 contracts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    /// Polygon PoS mainnet settling on ethereum mainnet.
+    Mainnet,
+    /// Amoy testnet settling on sepolia.
+    Amoy,
+}
+
+impl Network {
+    pub const ALL: [Network; 2] = [Network::Mainnet, Network::Amoy];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Amoy => "amoy",
+        }
+    }
+
+    /// Chain id of bor (as included in the checkpoint message).
+    pub fn bor_chain_id(&self) -> u64 {
+        match self {
+            Network::Mainnet => 137,
+            Network::Amoy => 80002,
+        }
+    }
+
+    /// Chain id of the L1 the network settles on.
+    pub fn l1_chain_id(&self) -> u64 {
+        match self {
+            Network::Mainnet => 1,
+            Network::Amoy => 11155111,
+        }
+    }
+
+    /// Root chain proxy on L1.
+    pub fn root_chain(&self) -> Address {
+        match self {
+            Network::Mainnet => address!("86E4Dc95c7FBdBf52e33D563BbDB00823894C287"),
+            Network::Amoy => address!("bd07D7E1E93c8d4b2a261327F3C28a8EA7167209"),
+        }
+    }
+
+    /// Stake manager proxy on L1.
+    pub fn stake_manager(&self) -> Address {
+        match self {
+            Network::Mainnet => address!("5e3Ef299fDDf15eAa0432E6e66473ace8c13D908"),
+            Network::Amoy => address!("4AE8f648B1Ec892B6cc68C89cc088583964d08bE"),
+        }
+    }
+
+    /// SP1 verifier (gateway) on L1 which the `PoSVerifier` verifies proofs with.
+    pub fn sp1_verifier(&self) -> Address {
+        match self {
+            Network::Mainnet => address!("3B6041173B80E77f038f3F2C0f9744f04837185e"),
+            Network::Amoy => address!("3B6041173B80E77f038f3F2C0f9744f04837185e"),
+        }
+    }
+
+    /// Public L1 rpc endpoint.
+    pub fn eth_rpc_url(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "https://ethereum-rpc.publicnode.com",
+            Network::Amoy => "https://ethereum-sepolia-rpc.publicnode.com",
+        }
+    }
+
+    /// Public bor rpc endpoint.
+    pub fn bor_rpc_url(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "https://polygon-rpc.com",
+            Network::Amoy => "https://rpc-amoy.polygon.technology",
+        }
+    }
+
+    /// Public heimdall rest endpoint.
+    pub fn heimdall_rest_endpoint(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "https://heimdall-api.polygon.technology",
+            Network::Amoy => "https://heimdall-api-amoy.polygon.technology",
+        }
+    }
+
+    /// Public tendermint rpc endpoint of heimdall.
+    pub fn tendermint_endpoint(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "https://tendermint-api.polygon.technology",
+            Network::Amoy => "https://tendermint-api-amoy.polygon.technology",
+        }
+    }
+
+    pub fn from_bor_chain_id(bor_chain_id: u64) -> Option<Network> {
+        Self::ALL
+            .into_iter()
+            .find(|network| network.bor_chain_id() == bor_chain_id)
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|network| network.name() == s)
+            .ok_or_else(|| format!("unknown network: {}", s))
+    }
+}
@@ -0,0 +1,29 @@
+use alloy_primitives::Address;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Producer {
+    pub id: u64,
+    pub signer: Address,
+    pub power: u64,
+}
+
+/// Bor span as committed by heimdall i.e. the range of bor blocks and the block producers
+/// selected (from the validator set) for producing them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub id: u64,
+    pub start_block: u64,
+    pub end_block: u64,
+    pub producers: Vec<Producer>,
+}
+
+impl Span {
+    pub fn contains(&self, number: u64) -> bool {
+        self.start_block <= number && number <= self.end_block
+    }
+
+    pub fn is_producer(&self, signer: &Address) -> bool {
+        self.producers.iter().any(|p| p.signer == *signer)
+    }
+}
@@ -0,0 +1,139 @@
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use serde::{Deserialize, Serialize};
+
+// Storage slots of the `StakeManager` (behind it's proxy) as per the `StakeManagerStorage`
+// layout of https://github.com/maticnetwork/contracts (contracts/staking/stakeManager). Can be
+// cross-checked using `forge inspect StakeManager storage-layout` at the revision of the
+// implementation deployed behind the proxy.
+pub const CURRENT_EPOCH_SLOT: u64 = 9;
+pub const VALIDATORS_SLOT: u64 = 26;
+pub const VALIDATOR_STATE_SLOT: u64 = 28;
+
+// Offsets of the fields (in slots) in the `Validator` struct. `contractAddress` (the
+// `ValidatorShare` contract) and `status` are packed in the same slot. The delegated
+// stake held by `ValidatorShare` is mirrored in `delegatedAmount` hence no slots of the
+// share contract need to be read.
+pub const VALIDATOR_AMOUNT_OFFSET: u64 = 0;
+pub const VALIDATOR_DEACTIVATION_EPOCH_OFFSET: u64 = 3;
+pub const VALIDATOR_SIGNER_OFFSET: u64 = 5;
+pub const VALIDATOR_STATUS_OFFSET: u64 = 6;
+pub const VALIDATOR_DELEGATED_AMOUNT_OFFSET: u64 = 10;
+
+// Bit offset of `status` in it's slot (right after `contractAddress`)
+pub const VALIDATOR_STATUS_SHIFT: usize = 160;
+
+// `Status.Active` in the `StakeManager`
+pub const VALIDATOR_STATUS_ACTIVE: u8 = 1;
+
+/// Proof of an account and some of it's storage slots against a state root as returned
+/// by `eth_getProof`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountProof {
+    pub nonce: u64,
+    pub balance: U256,
+    pub code_hash: B256,
+    pub storage_hash: B256,
+    pub account_proof: Vec<Bytes>,
+    pub storage_proofs: Vec<StorageProof>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageProof {
+    pub key: B256,
+    pub value: U256,
+    pub proof: Vec<Bytes>,
+}
+
+/// Slot of the `amount` field of `validatorState` i.e. the total active stake.
+pub fn total_stake_slot() -> B256 {
+    B256::from(U256::from(VALIDATOR_STATE_SLOT))
+}
+
+/// Slot of the `stakerCount` field of `validatorState` i.e. the number of active validators.
+pub fn staker_count_slot() -> B256 {
+    B256::from(U256::from(VALIDATOR_STATE_SLOT + 1))
+}
+
+/// Slot of `currentEpoch` i.e. the number of checkpoints submitted so far.
+pub fn current_epoch_slot() -> B256 {
+    B256::from(U256::from(CURRENT_EPOCH_SLOT))
+}
+
+/// Slot of a field (at `offset`) of `validators[validator_id]`.
+pub fn validator_slot(validator_id: u64, offset: u64) -> B256 {
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(&U256::from(validator_id).to_be_bytes::<32>());
+    preimage[32..].copy_from_slice(&U256::from(VALIDATORS_SLOT).to_be_bytes::<32>());
+    let base = U256::from_be_bytes(keccak256(preimage).0);
+    B256::from(base + U256::from(offset))
+}
+
+/// All the slots required to derive the signer, status and total stake of a validator.
+pub fn validator_slots(validator_id: u64) -> Vec<B256> {
+    vec![
+        validator_slot(validator_id, VALIDATOR_AMOUNT_OFFSET),
+        validator_slot(validator_id, VALIDATOR_DEACTIVATION_EPOCH_OFFSET),
+        validator_slot(validator_id, VALIDATOR_SIGNER_OFFSET),
+        validator_slot(validator_id, VALIDATOR_STATUS_OFFSET),
+        validator_slot(validator_id, VALIDATOR_DELEGATED_AMOUNT_OFFSET),
+    ]
+}
+
+/// Extracts the signer address from the value of the signer slot.
+pub fn decode_signer(value: U256) -> Address {
+    Address::from_word(B256::from(value))
+}
+
+/// Extracts the validator status from the value of the packed `contractAddress` and
+/// `status` slot.
+pub fn decode_status(value: U256) -> u8 {
+    (value >> VALIDATOR_STATUS_SHIFT).byte(0)
+}
+
+/// Checks if a validator is part of the active set as per the `StakeManager` i.e. it's status
+/// is active and it hasn't been deactivated yet.
+pub fn is_active_validator(status: u8, deactivation_epoch: U256, current_epoch: U256) -> bool {
+    status == VALIDATOR_STATUS_ACTIVE
+        && (deactivation_epoch.is_zero() || deactivation_epoch > current_epoch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolValue;
+
+    #[test]
+    fn test_validator_slot() {
+        // `validators` is a mapping, the struct of a validator starts at
+        // keccak256(abi.encode(id, VALIDATORS_SLOT))
+        let base = keccak256((U256::from(7), U256::from(VALIDATORS_SLOT)).abi_encode());
+        assert_eq!(validator_slot(7, 0), base);
+        assert_eq!(
+            U256::from_be_bytes(validator_slot(7, VALIDATOR_STATUS_OFFSET).0),
+            U256::from_be_bytes(base.0) + U256::from(VALIDATOR_STATUS_OFFSET)
+        );
+        assert_eq!(validator_slots(7).len(), 5);
+    }
+
+    #[test]
+    fn test_decode_validator() {
+        let signer = Address::repeat_byte(0xaa);
+        assert_eq!(
+            decode_signer(U256::from_be_slice(signer.as_slice())),
+            signer
+        );
+
+        // `contractAddress` in the lower 160 bits followed by `status`
+        let share = U256::from_be_slice(Address::repeat_byte(0xff).as_slice());
+        let packed = share | (U256::from(VALIDATOR_STATUS_ACTIVE) << VALIDATOR_STATUS_SHIFT);
+        assert_eq!(decode_status(packed), VALIDATOR_STATUS_ACTIVE);
+        assert_eq!(decode_status(share), 0);
+
+        let epoch = U256::from(100);
+        assert!(is_active_validator(1, U256::ZERO, epoch));
+        assert!(is_active_validator(1, U256::from(101), epoch));
+        assert!(!is_active_validator(1, epoch, epoch));
+        // Locked or inactive
+        assert!(!is_active_validator(2, U256::ZERO, epoch));
+    }
+}
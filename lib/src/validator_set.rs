@@ -0,0 +1,94 @@
+use alloy_primitives::{keccak256, Address, B256, U256};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Validator {
+    pub id: u64,
+    pub signer: Address,
+    pub stake: U256,
+}
+
+/// Set of active validators (sorted by id) as per the `StakeManager` at a given L1 block.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorSet {
+    pub validators: Vec<Validator>,
+}
+
+impl ValidatorSet {
+    /// Hash of the set committed in the public values i.e. keccak256 over the packed
+    /// (id, signer, stake) of all validators in order of their ids.
+    pub fn hash(&self) -> B256 {
+        let mut buf = Vec::with_capacity(self.validators.len() * 60);
+        for validator in self.validators.iter() {
+            buf.extend_from_slice(&validator.id.to_be_bytes());
+            buf.extend_from_slice(validator.signer.as_slice());
+            buf.extend_from_slice(&validator.stake.to_be_bytes::<32>());
+        }
+        keccak256(buf)
+    }
+
+    /// Inserts the validator or replaces the existing one with same id.
+    pub fn upsert(&mut self, validator: Validator) {
+        match self
+            .validators
+            .binary_search_by_key(&validator.id, |v| v.id)
+        {
+            Ok(index) => self.validators[index] = validator,
+            Err(index) => self.validators.insert(index, validator),
+        }
+    }
+
+    pub fn get_by_signer(&self, signer: &Address) -> Option<&Validator> {
+        self.validators.iter().find(|v| v.signer == *signer)
+    }
+
+    pub fn total_stake(&self) -> U256 {
+        self.validators.iter().map(|v| v.stake).sum()
+    }
+
+    pub fn len(&self) -> usize {
+        self.validators.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.validators.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validator(id: u64, stake: u64) -> Validator {
+        Validator {
+            id,
+            signer: Address::with_last_byte(id as u8),
+            stake: U256::from(stake),
+        }
+    }
+
+    #[test]
+    fn test_validator_set() {
+        let mut set = ValidatorSet::default();
+        set.upsert(validator(3, 30));
+        set.upsert(validator(1, 10));
+        set.upsert(validator(2, 20));
+        assert_eq!(
+            set.validators.iter().map(|v| v.id).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(set.total_stake(), U256::from(60));
+        assert_eq!(
+            set.get_by_signer(&Address::with_last_byte(2)),
+            Some(&validator(2, 20))
+        );
+
+        // A stake update replaces the validator and changes the hash
+        let mut updated = set.clone();
+        assert_eq!(set.hash(), updated.hash());
+        updated.upsert(validator(2, 25));
+        assert_eq!(updated.len(), 3);
+        assert_eq!(updated.total_stake(), U256::from(65));
+        assert_ne!(set.hash(), updated.hash());
+    }
+}
@@ -7,4 +7,18 @@ fn main() {
         ..Default::default()
     };
     build_program_with_args("../program", args);
+
+    let args = BuildArgs {
+        ignore_rust_version: true,
+        elf_name: "aggregation-proof".to_string(),
+        ..Default::default()
+    };
+    build_program_with_args("../aggregation-program", args);
+
+    let args = BuildArgs {
+        ignore_rust_version: true,
+        elf_name: "header-chain-proof".to_string(),
+        ..Default::default()
+    };
+    build_program_with_args("../header-chain-program", args);
 }
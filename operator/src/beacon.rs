@@ -0,0 +1,1060 @@
+use crate::http::{HttpClient, HttpConfig};
+use crate::types::u64_string;
+use alloy_primitives::{Address, Bytes, B256, U256};
+use alloy_provider::{Provider, ReqwestProvider};
+use alloy_rpc_types::BlockNumberOrTag;
+use eyre::{eyre, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tracing::debug;
+use zk_checkpoint_lib::beacon::{merkle_branch, merkle_root, sha256_pair, BeaconRootProof};
+
+// Index of `block_hash` in `ExecutionPayload` (17 fields, depth 5)
+const BLOCK_HASH_INDEX: usize = 12;
+
+// Index of `execution_payload` in `BeaconBlockBody` (12 fields, 13 since electra, depth 4)
+const EXECUTION_PAYLOAD_INDEX: usize = 9;
+
+// Index of `body_root` in `BeaconBlockHeader` (5 fields, depth 3)
+const BODY_ROOT_INDEX: usize = 4;
+
+// SSZ list limits of the block body (mainnet preset)
+const MAX_PROPOSER_SLASHINGS: u64 = 16;
+const MAX_DEPOSITS: u64 = 16;
+const DEPOSIT_PROOF_LENGTH: u64 = 33;
+const MAX_VOLUNTARY_EXITS: u64 = 16;
+const MAX_BLS_TO_EXECUTION_CHANGES: u64 = 16;
+const MAX_BLOB_COMMITMENTS_PER_BLOCK: u64 = 4096;
+const MAX_VALIDATORS_PER_COMMITTEE: u64 = 2048;
+const MAX_COMMITTEES_PER_SLOT: u64 = 64;
+const MAX_TRANSACTIONS_PER_PAYLOAD: u64 = 1 << 20;
+const MAX_BYTES_PER_TRANSACTION: u64 = 1 << 30;
+const MAX_WITHDRAWALS_PER_PAYLOAD: u64 = 16;
+const MAX_DEPOSIT_REQUESTS_PER_PAYLOAD: u64 = 8192;
+const MAX_WITHDRAWAL_REQUESTS_PER_PAYLOAD: u64 = 16;
+const MAX_CONSOLIDATION_REQUESTS_PER_PAYLOAD: u64 = 2;
+
+/// Forks whose block body can be merkleized, i.e. the ones since blobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fork {
+    Deneb,
+    Electra,
+}
+
+impl Fork {
+    fn from_version(version: &str) -> Result<Self> {
+        match version {
+            "deneb" => Ok(Fork::Deneb),
+            // Fulu didn't change the block body
+            "electra" | "fulu" => Ok(Fork::Electra),
+            _ => eyre::bail!("unsupported beacon block version: {}", version),
+        }
+    }
+
+    fn max_attester_slashings(self) -> u64 {
+        match self {
+            Fork::Deneb => 2,
+            Fork::Electra => 1,
+        }
+    }
+
+    fn max_attestations(self) -> u64 {
+        match self {
+            Fork::Deneb => 128,
+            Fork::Electra => 8,
+        }
+    }
+
+    // Limit of both the aggregation bits and the attesting indices, which span all committees
+    // of the slot since electra
+    fn max_attesters(self) -> u64 {
+        match self {
+            Fork::Deneb => MAX_VALIDATORS_PER_COMMITTEE,
+            Fork::Electra => MAX_VALIDATORS_PER_COMMITTEE * MAX_COMMITTEES_PER_SLOT,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BeaconHeaderResponse {
+    pub data: BeaconHeaderData,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BeaconHeaderData {
+    pub root: B256,
+    pub header: SignedBeaconBlockHeader,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignedBeaconBlockHeader {
+    pub message: BeaconBlockHeader,
+    pub signature: Bytes,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BeaconBlockHeader {
+    #[serde(with = "u64_string")]
+    pub slot: u64,
+    #[serde(with = "u64_string")]
+    pub proposer_index: u64,
+    pub parent_root: B256,
+    pub state_root: B256,
+    pub body_root: B256,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BeaconBlockResponse {
+    pub version: String,
+    pub data: SignedBeaconBlock,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignedBeaconBlock {
+    pub message: BeaconBlock,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BeaconBlock {
+    #[serde(with = "u64_string")]
+    pub slot: u64,
+    pub body: BeaconBlockBody,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BeaconBlockBody {
+    pub randao_reveal: Bytes,
+    pub eth1_data: Eth1Data,
+    pub graffiti: B256,
+    pub proposer_slashings: Vec<ProposerSlashing>,
+    pub attester_slashings: Vec<AttesterSlashing>,
+    pub attestations: Vec<Attestation>,
+    pub deposits: Vec<Deposit>,
+    pub voluntary_exits: Vec<SignedVoluntaryExit>,
+    pub sync_aggregate: SyncAggregate,
+    pub execution_payload: ExecutionPayload,
+    pub bls_to_execution_changes: Vec<SignedBlsToExecutionChange>,
+    pub blob_kzg_commitments: Vec<Bytes>,
+    /// Since electra.
+    pub execution_requests: Option<ExecutionRequests>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Eth1Data {
+    pub deposit_root: B256,
+    #[serde(with = "u64_string")]
+    pub deposit_count: u64,
+    pub block_hash: B256,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProposerSlashing {
+    pub signed_header_1: SignedBeaconBlockHeader,
+    pub signed_header_2: SignedBeaconBlockHeader,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttesterSlashing {
+    pub attestation_1: IndexedAttestation,
+    pub attestation_2: IndexedAttestation,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IndexedAttestation {
+    pub attesting_indices: Vec<String>,
+    pub data: AttestationData,
+    pub signature: Bytes,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Attestation {
+    pub aggregation_bits: Bytes,
+    pub data: AttestationData,
+    pub signature: Bytes,
+    /// Since electra.
+    pub committee_bits: Option<Bytes>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttestationData {
+    #[serde(with = "u64_string")]
+    pub slot: u64,
+    #[serde(with = "u64_string")]
+    pub index: u64,
+    pub beacon_block_root: B256,
+    pub source: Checkpoint,
+    pub target: Checkpoint,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Checkpoint {
+    #[serde(with = "u64_string")]
+    pub epoch: u64,
+    pub root: B256,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Deposit {
+    pub proof: Vec<B256>,
+    pub data: DepositData,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DepositData {
+    pub pubkey: Bytes,
+    pub withdrawal_credentials: B256,
+    #[serde(with = "u64_string")]
+    pub amount: u64,
+    pub signature: Bytes,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignedVoluntaryExit {
+    pub message: VoluntaryExit,
+    pub signature: Bytes,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VoluntaryExit {
+    #[serde(with = "u64_string")]
+    pub epoch: u64,
+    #[serde(with = "u64_string")]
+    pub validator_index: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncAggregate {
+    pub sync_committee_bits: Bytes,
+    pub sync_committee_signature: Bytes,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExecutionPayload {
+    pub parent_hash: B256,
+    pub fee_recipient: Address,
+    pub state_root: B256,
+    pub receipts_root: B256,
+    pub logs_bloom: Bytes,
+    pub prev_randao: B256,
+    #[serde(with = "u64_string")]
+    pub block_number: u64,
+    #[serde(with = "u64_string")]
+    pub gas_limit: u64,
+    #[serde(with = "u64_string")]
+    pub gas_used: u64,
+    #[serde(with = "u64_string")]
+    pub timestamp: u64,
+    pub extra_data: Bytes,
+    pub base_fee_per_gas: String,
+    pub block_hash: B256,
+    pub transactions: Vec<Bytes>,
+    pub withdrawals: Vec<Withdrawal>,
+    #[serde(with = "u64_string")]
+    pub blob_gas_used: u64,
+    #[serde(with = "u64_string")]
+    pub excess_blob_gas: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Withdrawal {
+    #[serde(with = "u64_string")]
+    pub index: u64,
+    #[serde(with = "u64_string")]
+    pub validator_index: u64,
+    pub address: Address,
+    #[serde(with = "u64_string")]
+    pub amount: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignedBlsToExecutionChange {
+    pub message: BlsToExecutionChange,
+    pub signature: Bytes,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlsToExecutionChange {
+    #[serde(with = "u64_string")]
+    pub validator_index: u64,
+    pub from_bls_pubkey: Bytes,
+    pub to_execution_address: Address,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExecutionRequests {
+    pub deposits: Vec<DepositRequest>,
+    pub withdrawals: Vec<WithdrawalRequest>,
+    pub consolidations: Vec<ConsolidationRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DepositRequest {
+    pub pubkey: Bytes,
+    pub withdrawal_credentials: B256,
+    #[serde(with = "u64_string")]
+    pub amount: u64,
+    pub signature: Bytes,
+    #[serde(with = "u64_string")]
+    pub index: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WithdrawalRequest {
+    pub source_address: Address,
+    pub validator_pubkey: Bytes,
+    #[serde(with = "u64_string")]
+    pub amount: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsolidationRequest {
+    pub source_address: Address,
+    pub source_pubkey: Bytes,
+    pub target_pubkey: Bytes,
+}
+
+pub struct BeaconClient {
+    beacon_urls: Vec<String>,
+    http_client: HttpClient,
+}
+
+impl BeaconClient {
+    pub fn new(beacon_url: String, http_config: HttpConfig) -> Result<Self> {
+        Ok(Self {
+            beacon_urls: vec![beacon_url],
+            http_client: HttpClient::new("beacon", http_config)?,
+        })
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        debug!(path, "Fetching from beacon node");
+        self.http_client.get_json(&self.beacon_urls, path).await
+    }
+
+    pub async fn fetch_header(&self, block_root: B256) -> Result<BeaconHeaderResponse> {
+        self.get(&format!("/eth/v1/beacon/headers/{}", block_root))
+            .await
+    }
+
+    pub async fn fetch_block(&self, block_root: B256) -> Result<BeaconBlockResponse> {
+        self.get(&format!("/eth/v2/beacon/blocks/{}", block_root))
+            .await
+    }
+
+    /// Fetches the beacon block with the given root and returns the hash of it's execution
+    /// block along with the branch of that hash against the root. Works for any block, unlike
+    /// the light client bootstrap which is only served for finalized epoch boundary blocks.
+    pub async fn fetch_block_hash_branch(&self, block_root: B256) -> Result<(B256, Vec<B256>)> {
+        let header = self.fetch_header(block_root).await?.data;
+        let block = self.fetch_block(block_root).await?;
+        let header = header.header.message;
+        if header.slot != block.data.message.slot {
+            eyre::bail!(
+                "beacon block {} slot mismatch, header: {}, block: {}",
+                block_root,
+                header.slot,
+                block.data.message.slot
+            );
+        }
+
+        // block hash -> execution payload root -> body root -> beacon root
+        let fork = Fork::from_version(&block.version)?;
+        let body = &block.data.message.body;
+        let execution_chunks = execution_payload_chunks(&body.execution_payload)?;
+        let body_chunks = body_chunks(body, fork)?;
+        let beacon_chunks = beacon_block_header_chunks(&header);
+        if merkle_root(&body_chunks, 4) != header.body_root {
+            eyre::bail!(
+                "body root mismatch of beacon block {} at slot {}, expected: {}, got: {}",
+                block_root,
+                header.slot,
+                header.body_root,
+                merkle_root(&body_chunks, 4)
+            );
+        }
+        if merkle_root(&beacon_chunks, 3) != block_root {
+            eyre::bail!("header root mismatch of beacon block {}", block_root);
+        }
+
+        let mut branch = merkle_branch(&execution_chunks, 5, BLOCK_HASH_INDEX);
+        branch.extend(merkle_branch(&body_chunks, 4, EXECUTION_PAYLOAD_INDEX));
+        branch.extend(merkle_branch(&beacon_chunks, 3, BODY_ROOT_INDEX));
+        Ok((body.execution_payload.block_hash, branch))
+    }
+}
+
+/// Builds the proof of the L1 block hash against the beacon root of the block it belongs to.
+/// The beacon root is the parent beacon root of the next L1 block and can be looked up from
+/// the EIP-4788 contract using it's timestamp.
+pub async fn fetch_beacon_root_proof(
+    beacon_client: &BeaconClient,
+    provider: &ReqwestProvider,
+    l1_block_number: u64,
+    l1_block_hash: B256,
+) -> Result<BeaconRootProof> {
+    let next_block = provider
+        .get_block_by_number(BlockNumberOrTag::Number(l1_block_number + 1), false)
+        .await?
+        .ok_or_else(|| eyre!("l1 block {} not found", l1_block_number + 1))?;
+    let beacon_root = next_block
+        .header
+        .parent_beacon_block_root
+        .ok_or_else(|| eyre!("l1 block {} has no beacon root", l1_block_number + 1))?;
+
+    let (block_hash, branch) = beacon_client.fetch_block_hash_branch(beacon_root).await?;
+    if block_hash != l1_block_hash {
+        eyre::bail!(
+            "beacon block execution hash mismatch, expected: {}, got: {}",
+            l1_block_hash,
+            block_hash
+        );
+    }
+
+    let proof = BeaconRootProof {
+        timestamp: next_block.header.timestamp,
+        beacon_root,
+        branch,
+    };
+    if !proof.verify(l1_block_hash) {
+        eyre::bail!(
+            "failed to build beacon root proof for l1 block {}",
+            l1_block_number
+        );
+    }
+
+    Ok(proof)
+}
+
+/// SSZ merkleization of the chunks into a tree sized for `limit` chunks. Only the non zero
+/// part of the tree is built, as the limits of lists are way larger than their contents.
+fn merkleize(chunks: &[B256], limit: u64) -> B256 {
+    let depth = limit.next_power_of_two().trailing_zeros();
+    let mut layer = chunks.to_vec();
+    let mut zero = B256::ZERO;
+    for _ in 0..depth {
+        if layer.len() % 2 == 1 {
+            layer.push(zero);
+        }
+        layer = layer
+            .chunks(2)
+            .map(|pair| sha256_pair(&pair[0], &pair[1]))
+            .collect();
+        zero = sha256_pair(&zero, &zero);
+    }
+    layer.first().copied().unwrap_or(zero)
+}
+
+fn mix_in_length(root: B256, length: usize) -> B256 {
+    sha256_pair(&root, &B256::from(U256::from(length).to_le_bytes::<32>()))
+}
+
+// Packs the bytes into chunks, zero padding the last one
+fn pack(bytes: &[u8]) -> Vec<B256> {
+    bytes
+        .chunks(32)
+        .map(|bytes| {
+            let mut chunk = [0u8; 32];
+            chunk[..bytes.len()].copy_from_slice(bytes);
+            B256::from(chunk)
+        })
+        .collect()
+}
+
+fn container_root(fields: &[B256]) -> B256 {
+    merkleize(fields, fields.len() as u64)
+}
+
+fn list_root(roots: &[B256], limit: u64) -> B256 {
+    mix_in_length(merkleize(roots, limit), roots.len())
+}
+
+// Fixed size byte vector e.g. a BLS pubkey or signature
+fn bytes_vector_root(bytes: &[u8]) -> B256 {
+    let chunks = pack(bytes);
+    merkleize(&chunks, chunks.len() as u64)
+}
+
+fn byte_list_root(bytes: &[u8], limit: u64) -> B256 {
+    mix_in_length(merkleize(&pack(bytes), limit.div_ceil(32)), bytes.len())
+}
+
+fn uint64_list_root(values: &[String], limit: u64) -> Result<B256> {
+    let mut bytes = Vec::with_capacity(values.len() * 8);
+    for value in values {
+        bytes.extend_from_slice(&value.parse::<u64>()?.to_le_bytes());
+    }
+    Ok(mix_in_length(
+        merkleize(&pack(&bytes), (limit * 8).div_ceil(32)),
+        values.len(),
+    ))
+}
+
+// Bitlists are encoded with a delimiting bit after the last bit, which isn't merkleized
+fn bitlist_root(bytes: &[u8], limit: u64) -> Result<B256> {
+    let last = match bytes.last() {
+        Some(last) if *last != 0 => *last,
+        _ => eyre::bail!(
+            "invalid bitlist: 0x{}",
+            alloy_primitives::hex::encode(bytes)
+        ),
+    };
+    let delimiter = 7 - last.leading_zeros() as usize;
+    let length = (bytes.len() - 1) * 8 + delimiter;
+    let mut bits = bytes.to_vec();
+    bits[bytes.len() - 1] ^= 1 << delimiter;
+    bits.truncate(length.div_ceil(8));
+    Ok(mix_in_length(
+        merkleize(&pack(&bits), limit.div_ceil(256)),
+        length,
+    ))
+}
+
+fn uint64_chunk(value: u64) -> B256 {
+    let mut chunk = [0u8; 32];
+    chunk[..8].copy_from_slice(&value.to_le_bytes());
+    B256::from(chunk)
+}
+
+fn uint256_chunk(value: &str) -> Result<B256> {
+    let value = U256::from_str_radix(value, 10)?;
+    Ok(B256::from(value.to_le_bytes::<32>()))
+}
+
+fn address_chunk(value: &Address) -> B256 {
+    let mut chunk = [0u8; 32];
+    chunk[..20].copy_from_slice(value.as_slice());
+    B256::from(chunk)
+}
+
+// `ByteVector[256]` merkleized into 8 chunks
+fn logs_bloom_root(value: &Bytes) -> Result<B256> {
+    if value.len() != 256 {
+        eyre::bail!("invalid logs bloom length: {}", value.len());
+    }
+    let chunks: Vec<B256> = value.chunks(32).map(B256::from_slice).collect();
+    Ok(merkle_root(&chunks, 3))
+}
+
+// `ByteList[32]` merkleized into a single chunk and mixed in with it's length
+fn extra_data_root(value: &Bytes) -> Result<B256> {
+    if value.len() > 32 {
+        eyre::bail!("invalid extra data length: {}", value.len());
+    }
+    Ok(byte_list_root(value, 32))
+}
+
+fn execution_payload_chunks(payload: &ExecutionPayload) -> Result<Vec<B256>> {
+    let transactions: Vec<B256> = payload
+        .transactions
+        .iter()
+        .map(|tx| byte_list_root(tx, MAX_BYTES_PER_TRANSACTION))
+        .collect();
+    let withdrawals: Vec<B256> = payload
+        .withdrawals
+        .iter()
+        .map(|withdrawal| {
+            container_root(&[
+                uint64_chunk(withdrawal.index),
+                uint64_chunk(withdrawal.validator_index),
+                address_chunk(&withdrawal.address),
+                uint64_chunk(withdrawal.amount),
+            ])
+        })
+        .collect();
+    Ok(vec![
+        payload.parent_hash,
+        address_chunk(&payload.fee_recipient),
+        payload.state_root,
+        payload.receipts_root,
+        logs_bloom_root(&payload.logs_bloom)?,
+        payload.prev_randao,
+        uint64_chunk(payload.block_number),
+        uint64_chunk(payload.gas_limit),
+        uint64_chunk(payload.gas_used),
+        uint64_chunk(payload.timestamp),
+        extra_data_root(&payload.extra_data)?,
+        uint256_chunk(&payload.base_fee_per_gas)?,
+        payload.block_hash,
+        list_root(&transactions, MAX_TRANSACTIONS_PER_PAYLOAD),
+        list_root(&withdrawals, MAX_WITHDRAWALS_PER_PAYLOAD),
+        uint64_chunk(payload.blob_gas_used),
+        uint64_chunk(payload.excess_blob_gas),
+    ])
+}
+
+fn beacon_block_header_chunks(header: &BeaconBlockHeader) -> Vec<B256> {
+    vec![
+        uint64_chunk(header.slot),
+        uint64_chunk(header.proposer_index),
+        header.parent_root,
+        header.state_root,
+        header.body_root,
+    ]
+}
+
+fn signed_header_root(header: &SignedBeaconBlockHeader) -> B256 {
+    container_root(&[
+        container_root(&beacon_block_header_chunks(&header.message)),
+        bytes_vector_root(&header.signature),
+    ])
+}
+
+fn checkpoint_root(checkpoint: &Checkpoint) -> B256 {
+    container_root(&[uint64_chunk(checkpoint.epoch), checkpoint.root])
+}
+
+fn attestation_data_root(data: &AttestationData) -> B256 {
+    container_root(&[
+        uint64_chunk(data.slot),
+        uint64_chunk(data.index),
+        data.beacon_block_root,
+        checkpoint_root(&data.source),
+        checkpoint_root(&data.target),
+    ])
+}
+
+fn indexed_attestation_root(attestation: &IndexedAttestation, fork: Fork) -> Result<B256> {
+    Ok(container_root(&[
+        uint64_list_root(&attestation.attesting_indices, fork.max_attesters())?,
+        attestation_data_root(&attestation.data),
+        bytes_vector_root(&attestation.signature),
+    ]))
+}
+
+fn attestation_root(attestation: &Attestation, fork: Fork) -> Result<B256> {
+    let mut fields = vec![
+        bitlist_root(&attestation.aggregation_bits, fork.max_attesters())?,
+        attestation_data_root(&attestation.data),
+        bytes_vector_root(&attestation.signature),
+    ];
+    if fork == Fork::Electra {
+        let committee_bits = attestation
+            .committee_bits
+            .as_ref()
+            .ok_or_else(|| eyre!("attestation without committee bits"))?;
+        fields.push(bytes_vector_root(committee_bits));
+    }
+    Ok(container_root(&fields))
+}
+
+fn execution_requests_root(requests: &ExecutionRequests) -> B256 {
+    let deposits: Vec<B256> = requests
+        .deposits
+        .iter()
+        .map(|deposit| {
+            container_root(&[
+                bytes_vector_root(&deposit.pubkey),
+                deposit.withdrawal_credentials,
+                uint64_chunk(deposit.amount),
+                bytes_vector_root(&deposit.signature),
+                uint64_chunk(deposit.index),
+            ])
+        })
+        .collect();
+    let withdrawals: Vec<B256> = requests
+        .withdrawals
+        .iter()
+        .map(|withdrawal| {
+            container_root(&[
+                address_chunk(&withdrawal.source_address),
+                bytes_vector_root(&withdrawal.validator_pubkey),
+                uint64_chunk(withdrawal.amount),
+            ])
+        })
+        .collect();
+    let consolidations: Vec<B256> = requests
+        .consolidations
+        .iter()
+        .map(|consolidation| {
+            container_root(&[
+                address_chunk(&consolidation.source_address),
+                bytes_vector_root(&consolidation.source_pubkey),
+                bytes_vector_root(&consolidation.target_pubkey),
+            ])
+        })
+        .collect();
+    container_root(&[
+        list_root(&deposits, MAX_DEPOSIT_REQUESTS_PER_PAYLOAD),
+        list_root(&withdrawals, MAX_WITHDRAWAL_REQUESTS_PER_PAYLOAD),
+        list_root(&consolidations, MAX_CONSOLIDATION_REQUESTS_PER_PAYLOAD),
+    ])
+}
+
+fn body_chunks(body: &BeaconBlockBody, fork: Fork) -> Result<Vec<B256>> {
+    let proposer_slashings: Vec<B256> = body
+        .proposer_slashings
+        .iter()
+        .map(|slashing| {
+            container_root(&[
+                signed_header_root(&slashing.signed_header_1),
+                signed_header_root(&slashing.signed_header_2),
+            ])
+        })
+        .collect();
+    let attester_slashings = body
+        .attester_slashings
+        .iter()
+        .map(|slashing| {
+            Ok(container_root(&[
+                indexed_attestation_root(&slashing.attestation_1, fork)?,
+                indexed_attestation_root(&slashing.attestation_2, fork)?,
+            ]))
+        })
+        .collect::<Result<Vec<B256>>>()?;
+    let attestations = body
+        .attestations
+        .iter()
+        .map(|attestation| attestation_root(attestation, fork))
+        .collect::<Result<Vec<B256>>>()?;
+    let deposits: Vec<B256> = body
+        .deposits
+        .iter()
+        .map(|deposit| {
+            container_root(&[
+                merkleize(&deposit.proof, DEPOSIT_PROOF_LENGTH),
+                container_root(&[
+                    bytes_vector_root(&deposit.data.pubkey),
+                    deposit.data.withdrawal_credentials,
+                    uint64_chunk(deposit.data.amount),
+                    bytes_vector_root(&deposit.data.signature),
+                ]),
+            ])
+        })
+        .collect();
+    let voluntary_exits: Vec<B256> = body
+        .voluntary_exits
+        .iter()
+        .map(|exit| {
+            container_root(&[
+                container_root(&[
+                    uint64_chunk(exit.message.epoch),
+                    uint64_chunk(exit.message.validator_index),
+                ]),
+                bytes_vector_root(&exit.signature),
+            ])
+        })
+        .collect();
+    let bls_to_execution_changes: Vec<B256> = body
+        .bls_to_execution_changes
+        .iter()
+        .map(|change| {
+            container_root(&[
+                container_root(&[
+                    uint64_chunk(change.message.validator_index),
+                    bytes_vector_root(&change.message.from_bls_pubkey),
+                    address_chunk(&change.message.to_execution_address),
+                ]),
+                bytes_vector_root(&change.signature),
+            ])
+        })
+        .collect();
+    let blob_kzg_commitments: Vec<B256> = body
+        .blob_kzg_commitments
+        .iter()
+        .map(|commitment| bytes_vector_root(commitment))
+        .collect();
+
+    let mut chunks = vec![
+        bytes_vector_root(&body.randao_reveal),
+        container_root(&[
+            body.eth1_data.deposit_root,
+            uint64_chunk(body.eth1_data.deposit_count),
+            body.eth1_data.block_hash,
+        ]),
+        body.graffiti,
+        list_root(&proposer_slashings, MAX_PROPOSER_SLASHINGS),
+        list_root(&attester_slashings, fork.max_attester_slashings()),
+        list_root(&attestations, fork.max_attestations()),
+        list_root(&deposits, MAX_DEPOSITS),
+        list_root(&voluntary_exits, MAX_VOLUNTARY_EXITS),
+        container_root(&[
+            bytes_vector_root(&body.sync_aggregate.sync_committee_bits),
+            bytes_vector_root(&body.sync_aggregate.sync_committee_signature),
+        ]),
+        container_root(&execution_payload_chunks(&body.execution_payload)?),
+        list_root(&bls_to_execution_changes, MAX_BLS_TO_EXECUTION_CHANGES),
+        list_root(&blob_kzg_commitments, MAX_BLOB_COMMITMENTS_PER_BLOCK),
+    ];
+    if fork == Fork::Electra {
+        let requests = body
+            .execution_requests
+            .as_ref()
+            .ok_or_else(|| eyre!("beacon block body without execution requests"))?;
+        chunks.push(execution_requests_root(requests));
+    }
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use zk_checkpoint_lib::beacon::verify_merkle_branch;
+
+    // Slot in the middle of an epoch, for which no light client bootstrap is served
+    const SLOT: u64 = 12345;
+
+    /// Local stand-in for a beacon node which answers each request with the next body.
+    async fn beacon_node(responses: Vec<String>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for body in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = Vec::new();
+                while !String::from_utf8_lossy(&buf).contains("\r\n\r\n") {
+                    let mut chunk = [0u8; 1024];
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    fn hex(len: usize, byte: u8) -> String {
+        format!("0x{}", alloy_primitives::hex::encode(vec![byte; len]))
+    }
+
+    fn attestation_data() -> Value {
+        json!({
+            "slot": "12344",
+            "index": "0",
+            "beacon_block_root": hex(32, 1),
+            "source": {"epoch": "384", "root": hex(32, 2)},
+            "target": {"epoch": "385", "root": hex(32, 3)}
+        })
+    }
+
+    fn signed_header(byte: u8) -> Value {
+        json!({
+            "message": {
+                "slot": "12000",
+                "proposer_index": "7",
+                "parent_root": hex(32, byte),
+                "state_root": hex(32, byte),
+                "body_root": hex(32, byte)
+            },
+            "signature": hex(96, byte)
+        })
+    }
+
+    /// Electra block at `SLOT` with an entry in every list of the body.
+    fn electra_block(block_hash: B256) -> Value {
+        let indexed_attestation = json!({
+            "attesting_indices": ["1", "2", "3"],
+            "data": attestation_data(),
+            "signature": hex(96, 4)
+        });
+        let payload = json!({
+            "parent_hash": hex(32, 20),
+            "fee_recipient": hex(20, 21),
+            "state_root": hex(32, 22),
+            "receipts_root": hex(32, 23),
+            "logs_bloom": hex(256, 0),
+            "prev_randao": hex(32, 24),
+            "block_number": "7000000",
+            "gas_limit": "36000000",
+            "gas_used": "21000",
+            "timestamp": "1700000000",
+            "extra_data": "0x6265616368",
+            "base_fee_per_gas": "1000000000",
+            "block_hash": block_hash,
+            "transactions": [hex(120, 25), hex(40, 26)],
+            "withdrawals": [{
+                "index": "1",
+                "validator_index": "2",
+                "address": hex(20, 27),
+                "amount": "3"
+            }],
+            "blob_gas_used": "131072",
+            "excess_blob_gas": "0"
+        });
+        json!({
+            "version": "electra",
+            "execution_optimistic": false,
+            "finalized": false,
+            "data": {
+                "message": {
+                    "slot": SLOT.to_string(),
+                    "proposer_index": "42",
+                    "parent_root": hex(32, 5),
+                    "state_root": hex(32, 6),
+                    "body": {
+                        "randao_reveal": hex(96, 7),
+                        "eth1_data": {
+                            "deposit_root": hex(32, 8),
+                            "deposit_count": "100",
+                            "block_hash": hex(32, 9)
+                        },
+                        "graffiti": hex(32, 10),
+                        "proposer_slashings": [{
+                            "signed_header_1": signed_header(11),
+                            "signed_header_2": signed_header(12)
+                        }],
+                        "attester_slashings": [{
+                            "attestation_1": indexed_attestation,
+                            "attestation_2": indexed_attestation
+                        }],
+                        "attestations": [{
+                            "aggregation_bits": "0x0b01",
+                            "data": attestation_data(),
+                            "signature": hex(96, 13),
+                            "committee_bits": "0x0100000000000000"
+                        }],
+                        "deposits": [{
+                            "proof": vec![hex(32, 14); 33],
+                            "data": {
+                                "pubkey": hex(48, 15),
+                                "withdrawal_credentials": hex(32, 16),
+                                "amount": "32000000000",
+                                "signature": hex(96, 17)
+                            }
+                        }],
+                        "voluntary_exits": [{
+                            "message": {"epoch": "380", "validator_index": "18"},
+                            "signature": hex(96, 18)
+                        }],
+                        "sync_aggregate": {
+                            "sync_committee_bits": hex(64, 0xff),
+                            "sync_committee_signature": hex(96, 19)
+                        },
+                        "execution_payload": payload,
+                        "bls_to_execution_changes": [{
+                            "message": {
+                                "validator_index": "28",
+                                "from_bls_pubkey": hex(48, 28),
+                                "to_execution_address": hex(20, 28)
+                            },
+                            "signature": hex(96, 28)
+                        }],
+                        "blob_kzg_commitments": [hex(48, 29)],
+                        "execution_requests": {
+                            "deposits": [{
+                                "pubkey": hex(48, 30),
+                                "withdrawal_credentials": hex(32, 30),
+                                "amount": "1",
+                                "signature": hex(96, 30),
+                                "index": "5"
+                            }],
+                            "withdrawals": [{
+                                "source_address": hex(20, 31),
+                                "validator_pubkey": hex(48, 31),
+                                "amount": "0"
+                            }],
+                            "consolidations": []
+                        }
+                    }
+                },
+                "signature": hex(96, 32)
+            }
+        })
+    }
+
+    /// Header of the block, with the body root computed from the block itself.
+    fn header(block: &Value) -> (B256, Value) {
+        let response: BeaconBlockResponse = serde_json::from_value(block.clone()).unwrap();
+        let body_chunks = body_chunks(&response.data.message.body, Fork::Electra).unwrap();
+        let header = BeaconBlockHeader {
+            slot: SLOT,
+            proposer_index: 42,
+            parent_root: B256::repeat_byte(5),
+            state_root: B256::repeat_byte(6),
+            body_root: merkle_root(&body_chunks, 4),
+        };
+        let root = merkle_root(&beacon_block_header_chunks(&header), 3);
+        let header = json!({
+            "data": {
+                "root": root,
+                "canonical": true,
+                "header": {
+                    "message": {
+                        "slot": SLOT.to_string(),
+                        "proposer_index": "42",
+                        "parent_root": header.parent_root,
+                        "state_root": header.state_root,
+                        "body_root": header.body_root
+                    },
+                    "signature": hex(96, 32)
+                }
+            }
+        });
+        (root, header)
+    }
+
+    #[tokio::test]
+    async fn test_block_hash_branch() {
+        let block_hash = B256::repeat_byte(0xbb);
+        let block = electra_block(block_hash);
+        let (root, header) = header(&block);
+        let url = beacon_node(vec![header.to_string(), block.to_string()]).await;
+
+        let (hash, branch) = BeaconClient::new(url, HttpConfig::default())
+            .unwrap()
+            .fetch_block_hash_branch(root)
+            .await
+            .unwrap();
+        assert_eq!(hash, block_hash);
+        let proof = BeaconRootProof {
+            timestamp: 0,
+            beacon_root: root,
+            branch,
+        };
+        assert!(proof.verify(block_hash));
+        assert!(!proof.verify(B256::repeat_byte(0xbc)));
+    }
+
+    #[tokio::test]
+    async fn test_block_hash_branch_body_mismatch() {
+        let block = electra_block(B256::repeat_byte(0xbb));
+        let (root, header) = header(&block);
+        // The node serves a different block than the one of the header
+        let other = electra_block(B256::repeat_byte(0xbc));
+        let url = beacon_node(vec![header.to_string(), other.to_string()]).await;
+
+        let err = BeaconClient::new(url, HttpConfig::default())
+            .unwrap()
+            .fetch_block_hash_branch(root)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with(&format!(
+            "body root mismatch of beacon block {} at slot {}",
+            root, SLOT
+        )));
+    }
+
+    #[test]
+    fn test_bitlist_root() {
+        // 3 bits set out of 4, followed by the delimiting bit
+        let root = bitlist_root(&[0b0001_0111], 2048).unwrap();
+        let mut chunk = [0u8; 32];
+        chunk[0] = 0b0111;
+        let expected = mix_in_length(merkleize(&[B256::from(chunk)], 8), 4);
+        assert_eq!(root, expected);
+        assert!(bitlist_root(&[0b0111, 0], 2048).is_err());
+    }
+
+    #[test]
+    fn test_merkleize() {
+        // Padding up to the limit is the same as merkleizing the zero chunks
+        let chunks: Vec<B256> = (1..=3u8).map(B256::with_last_byte).collect();
+        let mut padded = chunks.clone();
+        padded.resize(16, B256::ZERO);
+        assert_eq!(merkleize(&chunks, 16), merkle_root(&padded, 4));
+        assert_eq!(merkleize(&chunks, 9), merkle_root(&padded, 4));
+        assert!(verify_merkle_branch(
+            chunks[2],
+            &merkle_branch(&padded, 4, 2),
+            18,
+            merkleize(&chunks, 16)
+        ));
+    }
+}
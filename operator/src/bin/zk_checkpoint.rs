@@ -0,0 +1,14 @@
+use clap::Parser;
+use zk_checkpoint_operator::cli::{run, Cli};
+use zk_checkpoint_operator::logging;
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    dotenv::dotenv().ok();
+    let cli = Cli::parse();
+
+    // Setup the logger.
+    logging::init(cli.log_level.as_deref(), cli.log_format)?;
+
+    run(cli).await
+}
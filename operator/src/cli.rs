@@ -0,0 +1,1092 @@
+use crate::beacon::fetch_beacon_root_proof;
+use crate::config::{Config, NetworkConfig};
+use crate::contract::Simulation;
+use crate::exit::{check_verified, generate_exit_proof};
+use crate::inputs::{checkpoint_stdin, fetch_side_tx_sigs, generate_inputs, CheckpointParams};
+use crate::logging::LogFormat;
+use crate::metrics::{self, metrics};
+use crate::sources::{BorSource, MemorySource, Recorder, RpcBorSource, RpcL1Source};
+use crate::submit::{
+    bor_block_number, build_calldata, check_l1_anchor, check_network, load_destinations,
+    submit_to_all, SubmissionStatus, LEGACY_CHECKPOINT_GAS,
+};
+use crate::{AGGREGATION_ELF, CHECKPOINT_ELF, HEADER_CHAIN_ELF};
+
+use alloy_primitives::{B256, U256};
+use alloy_provider::Provider;
+use alloy_rpc_types::BlockNumberOrTag;
+use clap::{Args, Parser, Subcommand};
+use eyre::Result;
+use sp1_sdk::{HashableKey, ProverClient, SP1Proof, SP1ProofWithPublicValues, SP1Stdin};
+use std::fs::File;
+use std::io::BufReader;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::{field, info, info_span, instrument, warn, Instrument, Span};
+use zk_checkpoint_lib::span::{Producer, Span as BorSpan};
+use zk_checkpoint_lib::validator_set::ValidatorSet;
+use zk_checkpoint_lib::{CheckpointProofInput, PoSVerifier};
+
+/// Operator for proving Polygon PoS checkpoints and settling them on the verifier.
+#[derive(Parser, Debug)]
+#[clap(name = "zk-checkpoint", author, version, about, long_about = None)]
+pub struct Cli {
+    /// Path of the config file, defaults to `zk-checkpoint.toml` (if present).
+    #[clap(long, global = true)]
+    pub config: Option<String>,
+
+    /// Network (from the config) to use, defaults to `ZK_CHECKPOINT_NETWORK` or the
+    /// `default_network` of the config.
+    #[clap(long, global = true)]
+    pub network: Option<String>,
+
+    /// Log level or filter (e.g. `debug` or `info,zk_checkpoint_operator=debug`), defaults to
+    /// `RUST_LOG` or info for the operator only.
+    #[clap(long, global = true)]
+    pub log_level: Option<String>,
+
+    /// Format of the logs, defaults to `LOG_FORMAT` or text.
+    #[clap(long, global = true, value_enum)]
+    pub log_format: Option<LogFormat>,
+
+    /// Address to serve prometheus metrics on (at `/metrics`) while the command runs, e.g.
+    /// `0.0.0.0:9090`, defaults to `METRICS_ADDR`.
+    #[clap(long, global = true)]
+    pub metrics_addr: Option<String>,
+
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Fetch the inputs for proving a checkpoint and save them to a file.
+    Inputs(InputsArgs),
+    /// Execute the checkpoint program on the inputs (without proving) and report the cycles.
+    Execute(ExecuteArgs),
+    /// Generate a proof of the checkpoint from the inputs.
+    Prove(ProveArgs),
+    /// Aggregate compressed checkpoint proofs into a single proof settling all of them.
+    Aggregate(AggregateArgs),
+    /// Prove the bor headers after the end block of a checkpoint against it's block producers.
+    HeaderChain(HeaderChainArgs),
+    /// Generate the proof of the receipt of a bor tx (and one of it's logs) against the
+    /// checkpoint including it.
+    ExitProof(ExitProofArgs),
+    /// Verify a proof locally and submit it to the verifier.
+    Verify(VerifyArgs),
+    /// Submit a proof to the verifiers on multiple chains.
+    Submit(SubmitArgs),
+    /// Print the vkeys of the programs.
+    Vkey,
+    /// Compare the state of the verifier with heimdall and the local programs.
+    Status,
+    /// Keep the metrics of the verifier (last verified checkpoint and the lag behind heimdall)
+    /// up to date and serve them until stopped.
+    Monitor(MonitorArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct InputsArgs {
+    #[clap(long)]
+    checkpoint_id: u64,
+
+    #[clap(long)]
+    checkpoint_tx_hash: String,
+
+    #[clap(long)]
+    l1_block_number: u64,
+
+    /// Only warn if L1 already received the checkpoint (e.g. for proving old checkpoints)
+    /// instead of failing. It still has to match the header block on L1.
+    #[arg(long, default_value_t = false)]
+    allow_received: bool,
+
+    /// Generate the proof of the L1 block hash against it's beacon root (EIP-4788).
+    #[arg(long, default_value_t = false)]
+    beacon_root_proof: bool,
+
+    #[clap(long, default_value = "inputs.json")]
+    output: String,
+
+    /// Path the validator set (as used by `header-chain`) is saved to.
+    #[clap(long, default_value = "validator_set.json")]
+    validator_set_output: String,
+
+    /// Path the beacon root proof is saved to (with `--beacon-root-proof`).
+    #[clap(long, default_value = "beacon_root_proof.json")]
+    beacon_root_proof_output: String,
+
+    /// Read the heimdall, L1 and bor data from a fixture (as saved using `--record`) instead
+    /// of the network endpoints.
+    #[clap(long, conflicts_with = "record")]
+    fixture: Option<String>,
+
+    /// Save all the data fetched from the network endpoints to a fixture at the given path.
+    #[clap(long)]
+    record: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ExecuteArgs {
+    #[clap(long, default_value = "inputs.json")]
+    inputs: String,
+}
+
+#[derive(Args, Debug)]
+pub struct ProveArgs {
+    #[clap(long, default_value = "inputs.json")]
+    inputs: String,
+
+    /// Generate a compressed proof (to be aggregated later) instead of a plonk proof.
+    #[arg(long, default_value_t = false)]
+    compressed: bool,
+
+    /// Path to save the proof to, defaults to `proof.bin` (or `proof_compressed.bin`).
+    #[clap(long)]
+    output: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct AggregateArgs {
+    /// Comma separated paths of compressed checkpoint proofs in order of the checkpoints.
+    #[clap(long, value_delimiter = ',', required = true)]
+    proofs: Vec<String>,
+
+    /// Generate the (plonk) proof, otherwise the program is only executed.
+    #[arg(long, default_value_t = false)]
+    prove: bool,
+
+    #[clap(long, default_value = "proof_aggregated.bin")]
+    output: String,
+}
+
+#[derive(Args, Debug)]
+pub struct HeaderChainArgs {
+    /// Id of the checkpoint whose end block the header chain starts from.
+    #[clap(long)]
+    checkpoint_id: u64,
+
+    /// Bor block number up to which the header chain is proven.
+    #[clap(long)]
+    tip_block_number: u64,
+
+    /// Hash of the heimdall tx proposing the span of the tip block, whose votes are verified.
+    #[clap(long)]
+    span_tx_hash: String,
+
+    /// Path of the validator set (as saved by `inputs`) which the block producers are checked
+    /// against.
+    #[clap(long, default_value = "validator_set.json")]
+    validator_set: String,
+
+    /// Generate the (plonk) proof, otherwise the program is only executed.
+    #[arg(long, default_value_t = false)]
+    prove: bool,
+
+    #[clap(long, default_value = "proof_header_chain.bin")]
+    output: String,
+}
+
+#[derive(Args, Debug)]
+pub struct ExitProofArgs {
+    /// Hash of the bor transaction whose receipt is to be proven.
+    #[clap(long)]
+    tx: B256,
+
+    /// Index of the log (within the receipt) to be proven.
+    #[clap(long, default_value_t = 0)]
+    log_index: u64,
+
+    /// Id of the checkpoint containing the transaction, searched for if not provided.
+    #[clap(long)]
+    checkpoint_id: Option<u64>,
+
+    #[clap(long, default_value = "exit_proof.json")]
+    output: String,
+}
+
+#[derive(Args, Debug)]
+pub struct VerifyArgs {
+    #[clap(long, default_value = "proof.bin")]
+    proof: String,
+
+    /// Whether the proof aggregates multiple checkpoint proofs.
+    #[arg(long, default_value_t = false)]
+    aggregated: bool,
+
+    /// Simulate the submission (and report the expected gas) without sending a transaction.
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SubmitArgs {
+    #[clap(long, default_value = "proof.bin")]
+    proof: String,
+
+    /// Whether the proof aggregates multiple checkpoint proofs.
+    #[arg(long, default_value_t = false)]
+    aggregated: bool,
+
+    /// Path of the json file with the list of destinations, defaults to the verifier of the
+    /// selected network.
+    #[clap(long)]
+    destinations: Option<String>,
+
+    /// Comma separated names of destinations to submit to (e.g. to only retry failed ones).
+    #[clap(long, value_delimiter = ',')]
+    only: Vec<String>,
+
+    /// Number of times a failed submission is retried (per destination).
+    #[clap(long, default_value_t = 3)]
+    retries: u32,
+}
+
+#[derive(Args, Debug)]
+pub struct MonitorArgs {
+    /// Seconds between updates of the metrics.
+    #[clap(long, default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+    interval: u64,
+}
+
+pub async fn run(cli: Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    let network = || config.network(cli.network.as_deref());
+
+    let metrics_addr = cli.metrics_addr.or_else(|| {
+        std::env::var("METRICS_ADDR")
+            .ok()
+            .filter(|addr| !addr.is_empty())
+    });
+    if let Some(addr) = metrics_addr.as_ref() {
+        let addr = metrics::serve(addr).await?;
+        info!(%addr, "Serving metrics on /metrics");
+    } else if matches!(cli.command, Command::Monitor(_)) {
+        eyre::bail!(
+            "monitor needs an address to serve the metrics on, set --metrics-addr or METRICS_ADDR"
+        );
+    }
+
+    match cli.command {
+        Command::Inputs(args) => inputs(&network()?, args).await,
+        Command::Execute(args) => execute(args),
+        Command::Prove(args) => prove(args),
+        Command::Aggregate(args) => aggregate(args),
+        Command::HeaderChain(args) => header_chain(&network()?, args).await,
+        Command::ExitProof(args) => exit_proof(&network()?, args).await,
+        Command::Verify(args) => verify(&network()?, args).await,
+        Command::Submit(args) => submit(&network()?, args).await,
+        Command::Vkey => vkey(),
+        Command::Status => status(&network()?).await,
+        Command::Monitor(args) => monitor(&network()?, args).await,
+    }
+}
+
+fn load_inputs(path: &str) -> Result<CheckpointProofInput> {
+    let file = File::open(path).map_err(|err| eyre::eyre!("unable to open {}: {}", path, err))?;
+    Ok(serde_json::from_reader(BufReader::new(file))?)
+}
+
+fn load_proof(path: &str) -> Result<SP1ProofWithPublicValues> {
+    SP1ProofWithPublicValues::load(path)
+        .map_err(|err| eyre::eyre!("unable to load proof {}: {}", path, err))
+}
+
+#[instrument(
+    skip_all,
+    fields(
+        network = %config.name,
+        checkpoint_id = args.checkpoint_id,
+        l1_block_number = args.l1_block_number
+    )
+)]
+async fn inputs(config: &NetworkConfig, args: InputsArgs) -> Result<()> {
+    info!("Generating inputs");
+    let params = CheckpointParams {
+        checkpoint_id: args.checkpoint_id,
+        checkpoint_tx_hash: args.checkpoint_tx_hash,
+        l1_block_number: args.l1_block_number,
+        bor_chain_id: config.bor_chain_id()?,
+        root_chain: config.root_chain()?,
+        stake_manager: config.stake_manager()?,
+        allow_received: args.allow_received,
+    };
+    let (input, validator_set) = match args.fixture.as_ref() {
+        Some(path) => {
+            let fixture = MemorySource::load(path)?;
+            generate_inputs(&fixture, &fixture, &fixture, &params).await?
+        }
+        None => {
+            let heimdall = config.heimdall()?;
+            let l1 = RpcL1Source::new(config.eth_provider()?);
+            let bor = RpcBorSource::new(config.bor_provider()?);
+            match args.record.as_ref() {
+                Some(path) => {
+                    let recorder = Recorder::new(heimdall.as_ref(), &l1, &bor);
+                    let result = generate_inputs(&recorder, &recorder, &recorder, &params).await;
+                    // Save whatever was fetched, also when it failed (e.g. to reproduce it)
+                    recorder.recorded().save(path)?;
+                    info!(%path, "Fixture saved");
+                    result?
+                }
+                None => generate_inputs(heimdall.as_ref(), &l1, &bor, &params).await?,
+            }
+        }
+    };
+
+    // Save the validator set (whose hash is committed) e.g. for proving the header chain
+    let file = File::create(&args.validator_set_output)?;
+    serde_json::to_writer(file, &validator_set)?;
+    info!(path = %args.validator_set_output, "Validator set saved");
+
+    let file = File::create(&args.output)?;
+    serde_json::to_writer(file, &input)?;
+    info!(path = %args.output, "Inputs saved");
+
+    if args.beacon_root_proof {
+        let proof = fetch_beacon_root_proof(
+            &config.beacon_client()?,
+            &config.eth_provider()?,
+            args.l1_block_number,
+            input.l1_block_hash,
+        )
+        .instrument(info_span!("beacon_root_proof"))
+        .await?;
+        let file = File::create(&args.beacon_root_proof_output)?;
+        serde_json::to_writer(file, &proof)?;
+        info!(path = %args.beacon_root_proof_output, "Beacon root proof saved");
+    }
+
+    Ok(())
+}
+
+#[instrument(skip_all, fields(inputs = %args.inputs, cycles = field::Empty))]
+fn execute(args: ExecuteArgs) -> Result<()> {
+    let input = load_inputs(&args.inputs)?;
+
+    let client = ProverClient::new();
+    let (_, report) = client
+        .execute(CHECKPOINT_ELF, checkpoint_stdin(&input))
+        .run()
+        .map_err(|err| eyre::eyre!("failed to execute program: {}", err))?;
+    let cycles = report.total_instruction_count();
+    Span::current().record("cycles", cycles);
+    metrics().cycles.set(cycles as f64);
+    info!(cycles, "Executed program");
+
+    Ok(())
+}
+
+#[instrument(skip_all, fields(inputs = %args.inputs, compressed = args.compressed))]
+fn prove(args: ProveArgs) -> Result<()> {
+    let input = load_inputs(&args.inputs)?;
+    let stdin = checkpoint_stdin(&input);
+
+    let client = ProverClient::new();
+    let (pk, vk) = client.setup(CHECKPOINT_ELF);
+
+    info!("Generating proof");
+    let start = Instant::now();
+    let proof = if args.compressed {
+        client.prove(&pk, stdin).compressed().run()
+    } else {
+        client.prove(&pk, stdin).plonk().run()
+    }
+    .map_err(|err| eyre::eyre!("failed to generate proof: {}", err))?;
+    let elapsed = start.elapsed();
+
+    let output = args.output.unwrap_or_else(|| {
+        if args.compressed {
+            "proof_compressed.bin".to_string()
+        } else {
+            "proof.bin".to_string()
+        }
+    });
+    proof
+        .save(&output)
+        .map_err(|err| eyre::eyre!("failed to save proof: {}", err))?;
+    let size = std::fs::metadata(&output)?.len();
+    info!(path = %output, ?elapsed, size, "Proof saved");
+
+    metrics().proving_seconds.set(elapsed.as_secs_f64());
+    metrics().proof_size_bytes.set(size as f64);
+    // Inputs generated before the id was saved along with them don't have it
+    if input.checkpoint_id != 0 {
+        metrics()
+            .last_proved_checkpoint_id
+            .set(input.checkpoint_id as f64);
+    }
+
+    client
+        .verify(&proof, &vk)
+        .map_err(|err| eyre::eyre!("failed to verify proof: {}", err))?;
+    info!("Verified proof locally");
+
+    Ok(())
+}
+
+#[instrument(skip_all, fields(proofs = args.proofs.len(), prove = args.prove))]
+fn aggregate(args: AggregateArgs) -> Result<()> {
+    let client = ProverClient::new();
+    let (_, checkpoint_vk) = client.setup(CHECKPOINT_ELF);
+    let (pk, vk) = client.setup(AGGREGATION_ELF);
+
+    info!("Loading checkpoint proofs");
+    let proofs = args
+        .proofs
+        .iter()
+        .map(|path| load_proof(path))
+        .collect::<Result<Vec<_>>>()?;
+
+    // The program reads the vkey of the checkpoint program followed by the public values of
+    // all proofs, which are verified against the proofs themselves
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&checkpoint_vk.hash_u32());
+    let public_values: Vec<Vec<u8>> = proofs
+        .iter()
+        .map(|proof| proof.public_values.to_vec())
+        .collect();
+    stdin.write(&public_values);
+    for (path, proof) in args.proofs.iter().zip(proofs) {
+        let SP1Proof::Compressed(proof) = proof.proof else {
+            eyre::bail!(
+                "{} is not a compressed proof, generate it using `prove --compressed`",
+                path
+            );
+        };
+        stdin.write_proof(proof, checkpoint_vk.vk.clone());
+    }
+
+    let (_, report) = client
+        .execute(AGGREGATION_ELF, stdin.clone())
+        .run()
+        .map_err(|err| eyre::eyre!("failed to execute program: {}", err))?;
+    info!(
+        cycles = report.total_instruction_count(),
+        "Executed program"
+    );
+
+    if !args.prove {
+        info!("Skipping proving");
+        return Ok(());
+    }
+
+    info!("Generating proof");
+    let proof = client
+        .prove(&pk, stdin)
+        .plonk()
+        .run()
+        .map_err(|err| eyre::eyre!("failed to generate proof: {}", err))?;
+    proof
+        .save(&args.output)
+        .map_err(|err| eyre::eyre!("failed to save proof: {}", err))?;
+    info!(path = %args.output, "Proof saved");
+
+    client
+        .verify(&proof, &vk)
+        .map_err(|err| eyre::eyre!("failed to verify proof: {}", err))?;
+    info!("Verified proof locally");
+
+    Ok(())
+}
+
+#[instrument(
+    skip_all,
+    fields(
+        network = %config.name,
+        checkpoint_id = args.checkpoint_id,
+        tip_block_number = args.tip_block_number
+    )
+)]
+async fn header_chain(config: &NetworkConfig, args: HeaderChainArgs) -> Result<()> {
+    // Fetch the end block of the checkpoint which the header chain starts from
+    let heimdall = config.heimdall()?;
+    let checkpoint = heimdall.fetch_checkpoint_by_id(args.checkpoint_id).await?;
+    let checkpoint_bor_block_number = checkpoint.result.end_block;
+    if args.tip_block_number <= checkpoint_bor_block_number {
+        eyre::bail!(
+            "tip block {} should be after the checkpoint end block {}",
+            args.tip_block_number,
+            checkpoint_bor_block_number
+        );
+    }
+
+    // Fetch the span the headers belong to along with it's block producers
+    let span = heimdall
+        .fetch_span_by_block(args.tip_block_number)
+        .await?
+        .result;
+    if span.start_block > checkpoint_bor_block_number + 1 {
+        eyre::bail!(
+            "bor headers from {} to {} span across multiple spans",
+            checkpoint_bor_block_number + 1,
+            args.tip_block_number
+        );
+    }
+    let span = BorSpan {
+        id: span.span_id,
+        start_block: span.start_block,
+        end_block: span.end_block,
+        producers: span
+            .selected_producers
+            .iter()
+            .map(|p| Producer {
+                id: p.id,
+                signer: p.signer,
+                power: p.power,
+            })
+            .collect(),
+    };
+    info!(
+        span_id = span.id,
+        producers = span.producers.len(),
+        "Fetched span"
+    );
+    let tx = heimdall.fetch_tx_by_hash(args.span_tx_hash.clone()).await?;
+    let (span_sigs, span_signers) =
+        fetch_side_tx_sigs(heimdall.as_ref(), tx.result.hash, tx.result.height).await?;
+    info!(
+        signatures = span_sigs.len(),
+        "Fetched the span tx signatures"
+    );
+
+    info!(
+        from = checkpoint_bor_block_number,
+        to = args.tip_block_number,
+        "Fetching bor headers"
+    );
+    let bor = RpcBorSource::new(config.bor_provider()?);
+    let mut headers = bor
+        .headers(checkpoint_bor_block_number, args.tip_block_number)
+        .instrument(info_span!("bor_headers"))
+        .await?;
+    let checkpoint_bor_block_hash = headers.remove(0).hash_slow();
+
+    let file = File::open(&args.validator_set)
+        .map_err(|err| eyre::eyre!("unable to open {}: {}", args.validator_set, err))?;
+    let validator_set: ValidatorSet = serde_json::from_reader(BufReader::new(file))?;
+
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&checkpoint_bor_block_hash);
+    stdin.write(&checkpoint_bor_block_number);
+    stdin.write(&span);
+    stdin.write(&validator_set);
+    stdin.write(&span_sigs);
+    stdin.write(&span_signers);
+    stdin.write(&headers);
+
+    let client = ProverClient::new();
+    let (pk, vk) = client.setup(HEADER_CHAIN_ELF);
+
+    let (_, report) = client
+        .execute(HEADER_CHAIN_ELF, stdin.clone())
+        .run()
+        .map_err(|err| eyre::eyre!("failed to execute program: {}", err))?;
+    info!(
+        cycles = report.total_instruction_count(),
+        "Executed program"
+    );
+
+    if !args.prove {
+        info!("Skipping proving");
+        return Ok(());
+    }
+
+    info!("Generating proof");
+    let proof = client
+        .prove(&pk, stdin)
+        .plonk()
+        .run()
+        .map_err(|err| eyre::eyre!("failed to generate proof: {}", err))?;
+    proof
+        .save(&args.output)
+        .map_err(|err| eyre::eyre!("failed to save proof: {}", err))?;
+    info!(path = %args.output, "Proof saved");
+
+    client
+        .verify(&proof, &vk)
+        .map_err(|err| eyre::eyre!("failed to verify proof: {}", err))?;
+    info!("Verified proof locally");
+
+    Ok(())
+}
+
+#[instrument(skip_all, fields(network = %config.name, tx = %args.tx))]
+async fn exit_proof(config: &NetworkConfig, args: ExitProofArgs) -> Result<()> {
+    let heimdall = config.heimdall()?;
+    let provider = config.bor_provider()?;
+    let bor = RpcBorSource::new(provider.clone());
+    let proof = generate_exit_proof(
+        heimdall.as_ref(),
+        &bor,
+        &provider,
+        args.tx,
+        args.log_index,
+        args.checkpoint_id,
+    )
+    .await?;
+    // The proof is only useful once it's checkpoint is verified on L1
+    check_verified(&config.read_only_contract_client()?, &proof).await?;
+
+    let file = File::create(&args.output)?;
+    serde_json::to_writer(file, &proof)?;
+    info!(path = %args.output, "Exit proof saved");
+
+    Ok(())
+}
+
+#[instrument(
+    skip_all,
+    fields(
+        network = %config.name,
+        proof = %args.proof,
+        aggregated = args.aggregated,
+        dry_run = args.dry_run,
+        bor_block_number = field::Empty
+    )
+)]
+async fn verify(config: &NetworkConfig, args: VerifyArgs) -> Result<()> {
+    let proof = load_proof(&args.proof)?;
+
+    let client = ProverClient::new();
+    let (_, vk) = client.setup(if args.aggregated {
+        AGGREGATION_ELF
+    } else {
+        CHECKPOINT_ELF
+    });
+
+    client
+        .verify(&proof, &vk)
+        .map_err(|err| eyre::eyre!("failed to verify proof: {}", err))?;
+    info!("Verified proof locally");
+    Span::current().record(
+        "bor_block_number",
+        bor_block_number(&proof, args.aggregated)?,
+    );
+
+    if args.dry_run {
+        return simulate_onchain(config, proof, args.aggregated).await;
+    }
+
+    verify_onchain(config, proof, args.aggregated).await?;
+    info!("Verified proof on-chain");
+
+    Ok(())
+}
+
+async fn verify_onchain(
+    config: &NetworkConfig,
+    proof: SP1ProofWithPublicValues,
+    aggregated: bool,
+) -> Result<()> {
+    let contract_client = config.contract_client()?;
+    check_network(
+        &proof,
+        aggregated,
+        config.bor_chain_id()?,
+        config.stake_manager()?,
+    )?;
+
+    // Build the calldata from the commit in the proof and check if it can be anchored on L1
+    let (call_data, l1_block_number, l1_block_hash) = build_calldata(&proof, aggregated)?;
+    check_l1_anchor(&config.eth_provider()?, l1_block_number, l1_block_hash).await?;
+
+    // Fails (and exits with a non-zero code) if the tx reverts or isn't confirmed
+    let receipt = contract_client.send(call_data).await?;
+    metrics()
+        .submission_gas_used
+        .set_with(&config.name, receipt.gas_used as f64);
+    for checkpoint in contract_client.verified_checkpoints(&receipt) {
+        info!(
+            bor_block_number = %checkpoint.borBlockNumber,
+            bor_block_hash = %checkpoint.borBlockHash,
+            tx_hash = %receipt.transaction_hash,
+            "Verified bor block"
+        );
+    }
+
+    Ok(())
+}
+
+/// Simulates the on-chain verification of the proof using `eth_call` and reports the expected
+/// gas usage (compared to the legacy checkpoint submission) or the revert reason.
+async fn simulate_onchain(
+    config: &NetworkConfig,
+    proof: SP1ProofWithPublicValues,
+    aggregated: bool,
+) -> Result<()> {
+    let contract_client = config.contract_client()?;
+
+    if let Err(err) = check_network(
+        &proof,
+        aggregated,
+        config.bor_chain_id()?,
+        config.stake_manager()?,
+    ) {
+        warn!(%err, "Network check failed");
+    }
+    let (call_data, l1_block_number, l1_block_hash) = build_calldata(&proof, aggregated)?;
+    if let Err(err) = check_l1_anchor(&config.eth_provider()?, l1_block_number, l1_block_hash).await
+    {
+        warn!(%err, "L1 anchor check failed");
+    }
+
+    // Check if the proof has already been superseded by the last verified checkpoint
+    let bor_block_number = bor_block_number(&proof, aggregated)?;
+    let last_verified: u64 = contract_client
+        .call(&PoSVerifier::lastVerifiedBorBlockNumberCall {})
+        .await?
+        ._0
+        .to();
+    if bor_block_number <= last_verified {
+        warn!(
+            bor_block_number,
+            last_verified, "Bor block is already verified"
+        );
+    }
+
+    match contract_client.simulate(call_data).await? {
+        Simulation::Success {
+            gas,
+            max_fee_per_gas,
+        } => {
+            info!(
+                gas,
+                cost = gas * max_fee_per_gas,
+                max_fee_per_gas,
+                "Simulation succeeded"
+            );
+            info!(
+                legacy_gas = LEGACY_CHECKPOINT_GAS,
+                savings = %format!(
+                    "{:.2}%",
+                    100.0 * (1.0 - gas as f64 / LEGACY_CHECKPOINT_GAS as f64)
+                ),
+                "Compared to the legacy checkpoint submission"
+            );
+            Ok(())
+        }
+        Simulation::Reverted { reason } => eyre::bail!("simulation reverted: {}", reason),
+    }
+}
+
+#[instrument(
+    skip_all,
+    fields(
+        network = %config.name,
+        proof = %args.proof,
+        aggregated = args.aggregated,
+        bor_block_number = field::Empty
+    )
+)]
+async fn submit(config: &NetworkConfig, args: SubmitArgs) -> Result<()> {
+    let mut destinations = load_destinations(args.destinations.as_deref(), config)?;
+    if !args.only.is_empty() {
+        destinations.retain(|d| args.only.contains(&d.name));
+        if destinations.is_empty() {
+            eyre::bail!("no destinations matching: {:?}", args.only);
+        }
+    }
+
+    let proof = load_proof(&args.proof)?;
+    Span::current().record(
+        "bor_block_number",
+        bor_block_number(&proof, args.aggregated)?,
+    );
+    check_network(
+        &proof,
+        args.aggregated,
+        config.bor_chain_id()?,
+        config.stake_manager()?,
+    )?;
+    let (call_data, l1_block_number, l1_block_hash) = build_calldata(&proof, args.aggregated)?;
+
+    info!(destinations = destinations.len(), "Submitting proof");
+    let submissions = submit_to_all(
+        &destinations,
+        call_data,
+        l1_block_number,
+        l1_block_hash,
+        args.retries,
+    )
+    .await;
+
+    let mut failed = Vec::new();
+    for submission in submissions.iter() {
+        match &submission.status {
+            SubmissionStatus::Submitted { tx_hash } => info!(
+                destination = %submission.destination,
+                attempts = submission.attempts,
+                %tx_hash,
+                "Submitted proof"
+            ),
+            SubmissionStatus::Failed { error } => {
+                warn!(
+                    destination = %submission.destination,
+                    attempts = submission.attempts,
+                    %error,
+                    "Submission failed"
+                );
+                failed.push(submission.destination.clone());
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        eyre::bail!(
+            "submission failed for: {} (retry using --only {})",
+            failed.join(", "),
+            failed.join(",")
+        );
+    }
+
+    Ok(())
+}
+
+/// Vkeys of the checkpoint program, it's digest (as committed by the aggregation program) and
+/// of the aggregation program.
+fn local_vkeys() -> Result<(B256, B256, B256)> {
+    let client = ProverClient::new();
+    let (_, vk) = client.setup(CHECKPOINT_ELF);
+    let (_, aggregation_vk) = client.setup(AGGREGATION_ELF);
+
+    // The digest of the checkpoint program vkey is the concatenation of the big endian words
+    // of `hash_u32`.
+    let digest: Vec<u8> = vk.hash_u32().iter().flat_map(|w| w.to_be_bytes()).collect();
+    Ok((
+        B256::from_str(&vk.bytes32())?,
+        B256::from_slice(&digest),
+        B256::from_str(&aggregation_vk.bytes32())?,
+    ))
+}
+
+fn vkey() -> Result<()> {
+    let (vkey, vkey_digest, aggregation_vkey) = local_vkeys()?;
+    println!("Program Verification Key: {}", vkey);
+    println!(
+        "Aggregation Program Verification Key: {}, Program Verification Key Digest: {}",
+        aggregation_vkey, vkey_digest
+    );
+
+    Ok(())
+}
+
+/// Prints the state of the `PoSVerifier` and compares it against the latest heimdall checkpoint
+/// and the network and vkeys of the local programs. Fails if any of them don't match as proofs
+/// generated locally would be rejected on-chain.
+async fn status(config: &NetworkConfig) -> Result<()> {
+    // Only reads from the contract, so no key is needed
+    let contract_client = config.read_only_contract_client()?;
+
+    let sp1_verifier = contract_client
+        .call(&PoSVerifier::verifierCall {})
+        .await?
+        ._0;
+    let consensus_vkey = contract_client
+        .call(&PoSVerifier::consensusProofVKeyCall {})
+        .await?
+        ._0;
+    let aggregation_vkey = contract_client
+        .call(&PoSVerifier::aggregationProofVKeyCall {})
+        .await?
+        ._0;
+    let consensus_vkey_digest = contract_client
+        .call(&PoSVerifier::consensusProofVKeyDigestCall {})
+        .await?
+        ._0;
+    let last_verified_number: u64 = contract_client
+        .call(&PoSVerifier::lastVerifiedBorBlockNumberCall {})
+        .await?
+        ._0
+        .to();
+    let last_verified_hash = contract_client
+        .call(&PoSVerifier::lastVerifiedBorBlockHashCall {})
+        .await?
+        ._0;
+    let last_validator_set_hash = contract_client
+        .call(&PoSVerifier::lastValidatorSetHashCall {})
+        .await?
+        ._0;
+    let bor_chain_id = contract_client
+        .call(&PoSVerifier::borChainIdCall {})
+        .await?
+        ._0;
+    let stake_manager = contract_client
+        .call(&PoSVerifier::stakeManagerCall {})
+        .await?
+        ._0;
+
+    println!("Network: {}", config.name);
+    println!("PoSVerifier: {}", contract_client.contract);
+    println!("SP1 verifier: {}", sp1_verifier);
+    println!(
+        "Last verified bor block: {} ({})",
+        last_verified_number, last_verified_hash
+    );
+    println!("Last validator set hash: {}", last_validator_set_hash);
+
+    // Proofs of the configured network would be rejected by a verifier of another one
+    let mut mismatches = Vec::new();
+    if bor_chain_id != U256::from(config.bor_chain_id()?) {
+        println!(
+            "bor chain id: MISMATCH, on-chain: {}, config: {}",
+            bor_chain_id,
+            config.bor_chain_id()?
+        );
+        mismatches.push("bor chain id");
+    }
+    if stake_manager != config.stake_manager()? {
+        println!(
+            "stake manager: MISMATCH, on-chain: {}, config: {}",
+            stake_manager,
+            config.stake_manager()?
+        );
+        mismatches.push("stake manager");
+    }
+
+    // Compare against the latest checkpoint on heimdall
+    let client = config.heimdall()?;
+    let latest_id = client.fetch_checkpoint_count().await?;
+    let latest = client.fetch_checkpoint_by_id(latest_id).await?.result;
+    println!(
+        "Latest heimdall checkpoint: {} ({} to {})",
+        latest_id, latest.start_block, latest.end_block
+    );
+    if latest.end_block > last_verified_number {
+        match client
+            .find_checkpoint_by_block(last_verified_number)
+            .await?
+        {
+            Some(id) => {
+                metrics().last_submitted_checkpoint_id.set(id as f64);
+                // The checkpoint count may lag behind the search (e.g. behind a load balancer)
+                let lag = latest_id.saturating_sub(id);
+                metrics().heimdall_lag.set(lag as f64);
+                println!(
+                    "Verifier is {} checkpoints behind (last verified checkpoint: {})",
+                    lag, id
+                )
+            }
+            None => println!(
+                "Verifier is behind, no checkpoint found ending at the last verified block"
+            ),
+        }
+    } else {
+        metrics().heimdall_lag.set(0.0);
+        println!("Verifier is up to date");
+    }
+
+    // Check the last verified block hash against bor (if configured)
+    if config.bor_rpc_url.is_some() {
+        let bor_hash = config
+            .bor_provider()?
+            .get_block_by_number(BlockNumberOrTag::Number(last_verified_number), false)
+            .await?
+            .and_then(|block| block.header.hash.into());
+        if bor_hash != Some(last_verified_hash) && last_verified_number != 0 {
+            println!(
+                "WARNING: last verified bor block hash mismatch, bor: {:?}, verifier: {}",
+                bor_hash, last_verified_hash
+            );
+        }
+    }
+
+    // Compare the vkeys against the local programs
+    struct VKeyCheck {
+        name: &'static str,
+        onchain: B256,
+        local: B256,
+        // Unset (zero) on-chain unless the verifier accepts these proofs
+        optional: bool,
+    }
+    let (local_vkey, local_vkey_digest, local_aggregation_vkey) = local_vkeys()?;
+    let checks = [
+        VKeyCheck {
+            name: "consensus proof vkey",
+            onchain: consensus_vkey,
+            local: local_vkey,
+            optional: false,
+        },
+        VKeyCheck {
+            name: "aggregation proof vkey",
+            onchain: aggregation_vkey,
+            local: local_aggregation_vkey,
+            optional: true,
+        },
+        VKeyCheck {
+            name: "consensus proof vkey digest",
+            onchain: consensus_vkey_digest,
+            local: local_vkey_digest,
+            optional: true,
+        },
+    ];
+    for check in checks {
+        if check.optional && check.onchain.is_zero() {
+            println!("{}: not configured", check.name);
+        } else if check.onchain == check.local {
+            println!("{}: {} (matches local program)", check.name, check.onchain);
+        } else {
+            println!(
+                "{}: MISMATCH, on-chain: {}, local: {}",
+                check.name, check.onchain, check.local
+            );
+            mismatches.push(check.name);
+        }
+    }
+
+    if !mismatches.is_empty() {
+        eyre::bail!(
+            "mismatch for: {}, update the verifier (see `vkey`) or the config before submitting proofs",
+            mismatches.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Reads the last verified bor block from the verifier and records the checkpoint it belongs to
+/// and how many checkpoints the verifier is behind heimdall.
+async fn record_progress(config: &NetworkConfig) -> Result<()> {
+    let last_verified_number: u64 = config
+        .read_only_contract_client()?
+        .call(&PoSVerifier::lastVerifiedBorBlockNumberCall {})
+        .await?
+        ._0
+        .to();
+    let client = config.heimdall()?;
+    let latest_id = client.fetch_checkpoint_count().await?;
+    let id = client
+        .find_checkpoint_by_block(last_verified_number)
+        .await?
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "no checkpoint found containing the last verified bor block {}",
+                last_verified_number
+            )
+        })?;
+
+    metrics().last_submitted_checkpoint_id.set(id as f64);
+    metrics()
+        .heimdall_lag
+        .set(latest_id.saturating_sub(id) as f64);
+    info!(
+        last_verified_number,
+        id, latest_id, "Updated the verifier metrics"
+    );
+    Ok(())
+}
+
+/// Updates the verifier metrics every interval. Failures (e.g. heimdall being down) are logged
+/// and retried on the next update rather than stopping the monitor, they show up in the fetch
+/// errors.
+#[instrument(skip_all, fields(network = %config.name, interval = args.interval))]
+async fn monitor(config: &NetworkConfig, args: MonitorArgs) -> Result<()> {
+    let mut interval = tokio::time::interval(Duration::from_secs(args.interval));
+    loop {
+        interval.tick().await;
+        if let Err(err) = record_progress(config).await {
+            warn!(%err, "Unable to update the verifier metrics");
+        }
+    }
+}
@@ -0,0 +1,543 @@
+use crate::beacon::BeaconClient;
+use crate::contract::ContractClient;
+use crate::heimdall::HeimdallSource;
+use crate::http::HttpConfig;
+use crate::signer::{default_private_key_env, Signer, SignerConfig};
+use crate::utils::{split_endpoints, PoSClient};
+
+use alloy_primitives::Address;
+use alloy_provider::ReqwestProvider;
+use eyre::Result;
+use serde::Deserialize;
+use std::{collections::BTreeMap, env, fs, path::Path, str::FromStr};
+use url::Url;
+use zk_checkpoint_lib::network::Network;
+
+/// Config file read when no path is passed explicitly (if it exists).
+pub const DEFAULT_CONFIG_PATH: &str = "zk-checkpoint.toml";
+
+/// Operator config holding a profile for each network (e.g. mainnet, amoy, local). Profiles of
+/// the built-in networks (or with a `preset`) only need to set the values which differ.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Network used when none is selected via `--network` or `ZK_CHECKPOINT_NETWORK`.
+    pub default_network: Option<String>,
+    #[serde(default)]
+    pub networks: BTreeMap<String, NetworkConfig>,
+}
+
+/// Endpoints, contracts and signer of a single network. Each of them can be overridden via
+/// the env var with the same (uppercased) name.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkConfig {
+    #[serde(skip)]
+    pub name: String,
+    /// Built-in network whose values are used for the ones not set in the profile, defaults
+    /// to the network with the same name as the profile (if any).
+    pub preset: Option<Network>,
+    pub l1_chain_id: Option<u64>,
+    pub bor_chain_id: Option<u64>,
+    pub eth_rpc_url: Option<String>,
+    pub beacon_rpc_url: Option<String>,
+    pub bor_rpc_url: Option<String>,
+    /// Heimdall endpoints can be a comma separated list, where the ones after the first are
+    /// used as fallbacks.
+    pub heimdall_rest_endpoint: Option<String>,
+    pub tendermint_endpoint: Option<String>,
+    pub root_chain: Option<Address>,
+    pub stake_manager: Option<Address>,
+    pub verifier: Option<Address>,
+    pub signer: Option<SignerConfig>,
+}
+
+impl Config {
+    /// Loads the config from the given path or from `zk-checkpoint.toml` if it exists. Without
+    /// a config file, everything is read from env.
+    pub fn load(path: Option<&str>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => DEFAULT_CONFIG_PATH,
+            None => return Ok(Self::default()),
+        };
+        let content = fs::read_to_string(path)
+            .map_err(|err| eyre::eyre!("unable to read {}: {}", path, err))?;
+        toml::from_str(&content).map_err(|err| eyre::eyre!("invalid config {}: {}", path, err))
+    }
+
+    /// Returns the config of the selected network with the env overrides applied.
+    pub fn network(&self, name: Option<&str>) -> Result<NetworkConfig> {
+        let name = name
+            .map(str::to_string)
+            .or_else(|| env_var("ZK_CHECKPOINT_NETWORK"))
+            .or_else(|| self.default_network.clone());
+
+        let mut network = match name {
+            Some(name) => {
+                let profile = self.networks.get(&name).cloned();
+                let preset = profile
+                    .as_ref()
+                    .and_then(|profile| profile.preset)
+                    .or_else(|| Network::from_str(&name).ok());
+                let mut network = match (profile, preset) {
+                    (Some(profile), Some(preset)) => profile.or(NetworkConfig::preset(preset)),
+                    (Some(profile), None) => profile,
+                    (None, Some(preset)) => NetworkConfig::preset(preset),
+                    (None, None) => eyre::bail!(
+                        "network `{}` not found, available: {:?} and the built-in {:?}",
+                        name,
+                        self.networks.keys().collect::<Vec<_>>(),
+                        Network::ALL.map(|network| network.name())
+                    ),
+                };
+                network.name = name;
+                network
+            }
+            None if self.networks.is_empty() => NetworkConfig {
+                name: "env".to_string(),
+                ..Default::default()
+            },
+            None => eyre::bail!(
+                "no network selected, pass --network or set `default_network`, available: {:?}",
+                self.networks.keys().collect::<Vec<_>>()
+            ),
+        };
+        network.apply_env()?;
+
+        Ok(network)
+    }
+}
+
+/// Reads an env var treating empty values (e.g. `VERIFIER=` in `.env`) as unset.
+pub(crate) fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
+
+fn parse_env<T: FromStr>(name: &str) -> Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    env_var(name)
+        .map(|value| {
+            value
+                .trim()
+                .parse()
+                .map_err(|err| eyre::eyre!("invalid {}: {}", name, err))
+        })
+        .transpose()
+}
+
+impl NetworkConfig {
+    /// Config of a built-in network using the public endpoints.
+    pub fn preset(network: Network) -> Self {
+        Self {
+            name: network.name().to_string(),
+            preset: Some(network),
+            l1_chain_id: Some(network.l1_chain_id()),
+            bor_chain_id: Some(network.bor_chain_id()),
+            eth_rpc_url: Some(network.eth_rpc_url().to_string()),
+            beacon_rpc_url: None,
+            bor_rpc_url: Some(network.bor_rpc_url().to_string()),
+            heimdall_rest_endpoint: Some(network.heimdall_rest_endpoint().to_string()),
+            tendermint_endpoint: Some(network.tendermint_endpoint().to_string()),
+            root_chain: Some(network.root_chain()),
+            stake_manager: Some(network.stake_manager()),
+            // Changes with every deployment (e.g. for a new program), so it has to be configured
+            verifier: None,
+            signer: None,
+        }
+    }
+
+    /// Fills the values not set in this config from the other one.
+    fn or(self, other: Self) -> Self {
+        Self {
+            name: self.name,
+            preset: self.preset.or(other.preset),
+            l1_chain_id: self.l1_chain_id.or(other.l1_chain_id),
+            bor_chain_id: self.bor_chain_id.or(other.bor_chain_id),
+            eth_rpc_url: self.eth_rpc_url.or(other.eth_rpc_url),
+            beacon_rpc_url: self.beacon_rpc_url.or(other.beacon_rpc_url),
+            bor_rpc_url: self.bor_rpc_url.or(other.bor_rpc_url),
+            heimdall_rest_endpoint: self.heimdall_rest_endpoint.or(other.heimdall_rest_endpoint),
+            tendermint_endpoint: self.tendermint_endpoint.or(other.tendermint_endpoint),
+            root_chain: self.root_chain.or(other.root_chain),
+            stake_manager: self.stake_manager.or(other.stake_manager),
+            verifier: self.verifier.or(other.verifier),
+            signer: self.signer.or(other.signer),
+        }
+    }
+
+    /// Overrides the values of the config with the ones set in env.
+    fn apply_env(&mut self) -> Result<()> {
+        let urls = [
+            (&mut self.eth_rpc_url, "ETH_RPC_URL"),
+            (&mut self.beacon_rpc_url, "BEACON_RPC_URL"),
+            (&mut self.bor_rpc_url, "BOR_RPC_URL"),
+            (&mut self.heimdall_rest_endpoint, "HEIMDALL_REST_ENDPOINT"),
+            (&mut self.tendermint_endpoint, "TENDERMINT_ENDPOINT"),
+        ];
+        for (value, name) in urls {
+            if let Some(url) = env_var(name) {
+                *value = Some(url);
+            }
+        }
+        let addresses = [
+            (&mut self.root_chain, "ROOT_CHAIN"),
+            (&mut self.stake_manager, "STAKE_MANAGER"),
+            (&mut self.verifier, "VERIFIER"),
+        ];
+        for (value, name) in addresses {
+            if let Some(address) = parse_env(name)? {
+                *value = Some(address);
+            }
+        }
+        if let Some(chain_id) = parse_env("L1_CHAIN_ID")? {
+            self.l1_chain_id = Some(chain_id);
+        }
+        if let Some(chain_id) = parse_env("BOR_CHAIN_ID")? {
+            self.bor_chain_id = Some(chain_id);
+        }
+        if env_var("SIGNER").is_some() {
+            self.signer = Some(SignerConfig::from_env()?);
+        }
+
+        Ok(())
+    }
+
+    fn required<'a, T>(&self, value: &'a Option<T>, field: &str) -> Result<&'a T> {
+        value.as_ref().ok_or_else(|| {
+            eyre::eyre!(
+                "`{}` not set for network `{}` (set it in the config or via {})",
+                field,
+                self.name,
+                field.to_uppercase()
+            )
+        })
+    }
+
+    fn url(&self, value: &Option<String>, field: &str) -> Result<Url> {
+        let url = self.required(value, field)?;
+        Url::parse(url)
+            .map_err(|err| eyre::eyre!("invalid `{}` for network `{}`: {}", field, self.name, err))
+    }
+
+    pub fn l1_chain_id(&self) -> Result<u64> {
+        self.required(&self.l1_chain_id, "l1_chain_id").copied()
+    }
+
+    pub fn bor_chain_id(&self) -> Result<u64> {
+        self.required(&self.bor_chain_id, "bor_chain_id").copied()
+    }
+
+    pub fn root_chain(&self) -> Result<Address> {
+        self.required(&self.root_chain, "root_chain").copied()
+    }
+
+    pub fn stake_manager(&self) -> Result<Address> {
+        self.required(&self.stake_manager, "stake_manager").copied()
+    }
+
+    pub fn verifier(&self) -> Result<Address> {
+        self.required(&self.verifier, "verifier").copied()
+    }
+
+    pub fn eth_rpc_url(&self) -> Result<Url> {
+        self.url(&self.eth_rpc_url, "eth_rpc_url")
+    }
+
+    /// Provider for the L1 the verifier (and the PoS contracts) are deployed on.
+    pub fn eth_provider(&self) -> Result<ReqwestProvider> {
+        Ok(ReqwestProvider::new_http(self.eth_rpc_url()?))
+    }
+
+    pub fn bor_provider(&self) -> Result<ReqwestProvider> {
+        Ok(ReqwestProvider::new_http(
+            self.url(&self.bor_rpc_url, "bor_rpc_url")?,
+        ))
+    }
+
+    /// Parses a comma separated list of endpoints.
+    fn urls(&self, value: &Option<String>, field: &str) -> Result<Vec<String>> {
+        let urls = split_endpoints(self.required(value, field)?);
+        if urls.is_empty() {
+            eyre::bail!("`{}` is empty for network `{}`", field, self.name);
+        }
+        for url in urls.iter() {
+            Url::parse(url).map_err(|err| {
+                eyre::eyre!("invalid `{}` for network `{}`: {}", field, self.name, err)
+            })?;
+        }
+        Ok(urls)
+    }
+
+    pub fn pos_client(&self) -> Result<PoSClient> {
+        PoSClient::new(
+            self.urls(&self.heimdall_rest_endpoint, "heimdall_rest_endpoint")?,
+            self.urls(&self.tendermint_endpoint, "tendermint_endpoint")?,
+            HttpConfig::from_env()?,
+        )
+    }
+
+    /// Heimdall source of the network i.e. the rest api and tendermint rpc.
+    pub fn heimdall(&self) -> Result<Box<dyn HeimdallSource>> {
+        Ok(Box::new(self.pos_client()?))
+    }
+
+    pub fn beacon_client(&self) -> Result<BeaconClient> {
+        let beacon_url = self.url(&self.beacon_rpc_url, "beacon_rpc_url")?;
+        BeaconClient::new(
+            beacon_url.as_str().trim_end_matches('/').to_string(),
+            HttpConfig::from_env()?,
+        )
+    }
+
+    /// Signer configured for the network, defaults to the private key in `PRIVATE_KEY`.
+    pub fn signer_config(&self) -> SignerConfig {
+        self.signer.clone().unwrap_or(SignerConfig::PrivateKey {
+            env: default_private_key_env(),
+        })
+    }
+
+    /// Client for sending txs to the verifier using the configured signer.
+    pub fn contract_client(&self) -> Result<ContractClient> {
+        let signer = self.signer_config().signer()?;
+        self.contract_client_with_signer(signer)
+    }
+
+    /// Client for only reading from the verifier, doesn't need any key.
+    pub fn read_only_contract_client(&self) -> Result<ContractClient> {
+        self.contract_client_with_signer(Signer::ReadOnly(Address::ZERO))
+    }
+
+    fn contract_client_with_signer(&self, signer: Signer) -> Result<ContractClient> {
+        ContractClient::with_signer(
+            self.l1_chain_id()?,
+            self.eth_rpc_url()?.as_str(),
+            signer,
+            &self.verifier()?.to_string(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // The env is shared by all tests, so the ones changing it run one at a time
+    static ENV: Mutex<()> = Mutex::new(());
+
+    const ENV_VARS: [&str; 12] = [
+        "ZK_CHECKPOINT_NETWORK",
+        "ETH_RPC_URL",
+        "BEACON_RPC_URL",
+        "BOR_RPC_URL",
+        "HEIMDALL_REST_ENDPOINT",
+        "TENDERMINT_ENDPOINT",
+        "ROOT_CHAIN",
+        "STAKE_MANAGER",
+        "VERIFIER",
+        "L1_CHAIN_ID",
+        "BOR_CHAIN_ID",
+        "SIGNER",
+    ];
+
+    fn with_env<T>(vars: &[(&str, &str)], f: impl FnOnce() -> T) -> T {
+        let _guard = ENV.lock().unwrap_or_else(|err| err.into_inner());
+        for name in ENV_VARS {
+            env::remove_var(name);
+        }
+        for (name, value) in vars {
+            env::set_var(name, value);
+        }
+        let result = f();
+        for (name, _) in vars {
+            env::remove_var(name);
+        }
+        result
+    }
+
+    fn config() -> Config {
+        toml::from_str(
+            r#"
+            default_network = "staging"
+
+            [networks.amoy]
+            bor_rpc_url = "http://localhost:8545"
+            verifier = "0x0000000000000000000000000000000000000001"
+
+            [networks.staging]
+            preset = "mainnet"
+            eth_rpc_url = "http://localhost:8546"
+
+            [networks.local]
+            l1_chain_id = 1337
+            eth_rpc_url = "http://localhost:8547"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_network_merges_preset() {
+        let config = config();
+        with_env(&[], || {
+            // Profile with the name of a built-in network
+            let amoy = config.network(Some("amoy")).unwrap();
+            assert_eq!(amoy.name, "amoy");
+            assert_eq!(amoy.bor_rpc_url.as_deref(), Some("http://localhost:8545"));
+            assert_eq!(amoy.verifier().unwrap(), Address::with_last_byte(1));
+            assert_eq!(amoy.l1_chain_id().unwrap(), Network::Amoy.l1_chain_id());
+            assert_eq!(amoy.stake_manager().unwrap(), Network::Amoy.stake_manager());
+
+            // Default network using an explicit preset
+            let staging = config.network(None).unwrap();
+            assert_eq!(staging.name, "staging");
+            assert_eq!(
+                staging.eth_rpc_url().unwrap().as_str(),
+                "http://localhost:8546/"
+            );
+            assert_eq!(
+                staging.bor_chain_id().unwrap(),
+                Network::Mainnet.bor_chain_id()
+            );
+            assert_eq!(staging.root_chain().unwrap(), Network::Mainnet.root_chain());
+
+            // Built-in network without a profile
+            let mainnet = config.network(Some("mainnet")).unwrap();
+            assert_eq!(mainnet.name, "mainnet");
+            assert_eq!(
+                mainnet.eth_rpc_url().unwrap().as_str(),
+                Url::parse(Network::Mainnet.eth_rpc_url()).unwrap().as_str()
+            );
+            assert!(mainnet.verifier.is_none());
+
+            // Profile without a preset only has it's own values
+            let local = config.network(Some("local")).unwrap();
+            assert_eq!(local.l1_chain_id().unwrap(), 1337);
+            assert!(local.bor_chain_id.is_none());
+            assert!(local.preset.is_none());
+
+            let err = config.network(Some("unknown")).unwrap_err();
+            assert!(err.to_string().starts_with("network `unknown` not found"));
+        });
+    }
+
+    #[test]
+    fn test_network_selection() {
+        let config = config();
+        with_env(&[("ZK_CHECKPOINT_NETWORK", "local")], || {
+            assert_eq!(config.network(None).unwrap().name, "local");
+            // The argument takes precedence over env
+            assert_eq!(config.network(Some("amoy")).unwrap().name, "amoy");
+        });
+
+        with_env(&[], || {
+            let config = Config {
+                default_network: None,
+                ..config
+            };
+            let err = config.network(None).unwrap_err();
+            assert!(err.to_string().starts_with("no network selected"));
+
+            // Without any profile everything is read from env
+            let network = Config::default().network(None).unwrap();
+            assert_eq!(network.name, "env");
+            assert!(network.eth_rpc_url.is_none());
+        });
+    }
+
+    #[test]
+    fn test_network_env_overrides() {
+        let config = config();
+        let vars = [
+            ("BOR_RPC_URL", "http://localhost:9545"),
+            ("BOR_CHAIN_ID", " 42 "),
+            (
+                "STAKE_MANAGER",
+                "0x0000000000000000000000000000000000000002",
+            ),
+            // Empty values (e.g. `VERIFIER=` in `.env`) are treated as unset
+            ("VERIFIER", ""),
+            ("ETH_RPC_URL", "  "),
+        ];
+        with_env(&vars, || {
+            let amoy = config.network(Some("amoy")).unwrap();
+            assert_eq!(amoy.bor_rpc_url.as_deref(), Some("http://localhost:9545"));
+            assert_eq!(amoy.bor_chain_id().unwrap(), 42);
+            assert_eq!(amoy.stake_manager().unwrap(), Address::with_last_byte(2));
+            assert_eq!(amoy.verifier().unwrap(), Address::with_last_byte(1));
+            assert_eq!(
+                amoy.eth_rpc_url.as_deref(),
+                Some(Network::Amoy.eth_rpc_url())
+            );
+        });
+
+        with_env(&[("L1_CHAIN_ID", "sepolia")], || {
+            let err = config.network(Some("amoy")).unwrap_err();
+            assert!(
+                err.to_string().starts_with("invalid L1_CHAIN_ID"),
+                "{}",
+                err
+            );
+        });
+        with_env(&[("ROOT_CHAIN", "0x1234")], || {
+            let err = config.network(Some("amoy")).unwrap_err();
+            assert!(err.to_string().starts_with("invalid ROOT_CHAIN"), "{}", err);
+        });
+    }
+
+    #[test]
+    fn test_missing_and_invalid_fields() {
+        let network = NetworkConfig {
+            name: "local".to_string(),
+            eth_rpc_url: Some("localhost".to_string()),
+            heimdall_rest_endpoint: Some("http://localhost:1317, not a url".to_string()),
+            tendermint_endpoint: Some(" , ".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            network.verifier().unwrap_err().to_string(),
+            "`verifier` not set for network `local` (set it in the config or via VERIFIER)"
+        );
+        assert!(network
+            .eth_rpc_url()
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid `eth_rpc_url` for network `local`"));
+        assert!(network
+            .urls(&network.heimdall_rest_endpoint, "heimdall_rest_endpoint")
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid `heimdall_rest_endpoint` for network `local`"));
+        assert_eq!(
+            network
+                .urls(&network.tendermint_endpoint, "tendermint_endpoint")
+                .unwrap_err()
+                .to_string(),
+            "`tendermint_endpoint` is empty for network `local`"
+        );
+        assert_eq!(
+            network
+                .urls(&network.bor_rpc_url, "bor_rpc_url")
+                .unwrap_err()
+                .to_string(),
+            "`bor_rpc_url` not set for network `local` (set it in the config or via BOR_RPC_URL)"
+        );
+
+        let network = NetworkConfig {
+            heimdall_rest_endpoint: Some(
+                "http://localhost:1317, http://localhost:1318".to_string(),
+            ),
+            ..network
+        };
+        assert_eq!(
+            network
+                .urls(&network.heimdall_rest_endpoint, "heimdall_rest_endpoint")
+                .unwrap(),
+            ["http://localhost:1317", "http://localhost:1318"]
+        );
+    }
+}
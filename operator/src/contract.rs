@@ -1,76 +1,801 @@
-use ethers::{
-    middleware::SignerMiddleware,
-    providers::{Http, Middleware, Provider},
-    signers::{LocalWallet, Signer},
-    types::{
-        transaction::eip2718::TypedTransaction, Address, TransactionReceipt, TransactionRequest,
-    },
-};
+use crate::config::env_var;
+use crate::signer::Signer;
+
+use alloy_network::TransactionBuilder;
+use alloy_primitives::{hex, Address, Bytes, B256};
+use alloy_provider::{Provider, ReqwestProvider};
+use alloy_rpc_types::{BlockId, TransactionReceipt, TransactionRequest};
+use alloy_sol_types::{sol, SolCall, SolEvent, SolInterface};
 use eyre::Result;
-use std::env;
+use std::{fmt, sync::Mutex, time::Duration};
+use tracing::{info, warn};
+use url::Url;
+use zk_checkpoint_lib::PoSVerifier;
 
-pub struct ContractClient {
-    chain_id: u64,
-    client: SignerMiddleware<Provider<Http>, LocalWallet>,
-    pub contract: Address,
+sol! {
+    interface ISP1Verifier {
+        error WrongVerifierSelector(bytes4 received, bytes4 expected);
+        error InvalidProof();
+    }
+}
+
+/// Settings for sending transactions and waiting for them to be confirmed.
+#[derive(Debug, Clone)]
+pub struct TxConfig {
+    /// Number of confirmations to wait for.
+    pub confirmations: u64,
+    /// Time to wait for a transaction to be confirmed before bumping it's fees.
+    pub timeout: Duration,
+    /// Number of times the fees of a stuck transaction are bumped before giving up.
+    pub max_fee_bumps: u32,
+    /// Percentage by which the fees are bumped (nodes require at least 10% for replacement).
+    pub fee_bump_percent: u128,
 }
 
-impl Default for ContractClient {
+impl Default for TxConfig {
     fn default() -> Self {
-        let chain_id = env::var("L1_CHAIN_ID")
-            .expect("L1_CHAIN_ID not set")
-            .parse::<u64>()
-            .expect("L1_CHAIN_ID not a valid u64");
-        let rpc_url = env::var("ETH_RPC_URL").expect("ETH_RPC_URL not set");
-        let mut private_key = env::var("PRIVATE_KEY").expect("PRIVATE_KEY not set");
-        // Strip the `0x` prefix from the private key (if present).
-        if let Some(stripped) = private_key.strip_prefix("0x") {
-            private_key = stripped.to_string();
+        Self {
+            confirmations: 1,
+            timeout: Duration::from_secs(120),
+            max_fee_bumps: 3,
+            fee_bump_percent: 20,
+        }
+    }
+}
+
+impl TxConfig {
+    /// Reads the config from the `TX_CONFIRMATIONS`, `TX_TIMEOUT`, `TX_MAX_FEE_BUMPS` and
+    /// `TX_FEE_BUMP_PERCENT` env vars falling back to the defaults.
+    pub fn from_env() -> Result<Self> {
+        let mut config = Self::default();
+        if let Some(value) = env_var("TX_CONFIRMATIONS") {
+            config.confirmations = value.trim().parse()?;
+        }
+        if let Some(value) = env_var("TX_TIMEOUT") {
+            config.timeout = Duration::from_secs(value.trim().parse()?);
+        }
+        if let Some(value) = env_var("TX_MAX_FEE_BUMPS") {
+            config.max_fee_bumps = value.trim().parse()?;
+        }
+        if let Some(value) = env_var("TX_FEE_BUMP_PERCENT") {
+            config.fee_bump_percent = value.trim().parse()?;
+        }
+        Ok(config)
+    }
+}
+
+/// Error returned when a transaction reverts (either during gas estimation or on-chain).
+/// Reverts are not worth retrying as they'll revert again with the same inputs.
+#[derive(Debug, Clone)]
+pub struct RevertError {
+    pub tx_hash: Option<B256>,
+    pub reason: String,
+}
+
+impl fmt::Display for RevertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.tx_hash {
+            Some(tx_hash) => write!(f, "tx {} reverted: {}", tx_hash, self.reason),
+            None => write!(f, "tx reverted: {}", self.reason),
         }
-        let contract = env::var("VERIFIER").expect("VERIFIER not set");
+    }
+}
+
+impl std::error::Error for RevertError {}
 
-        Self::new(chain_id, &rpc_url, &private_key, &contract)
-            .expect("Failed to create ContractClient")
+/// Decodes the revert data into a readable reason i.e. the SP1 verifier errors or the
+/// `Error(string)` and `Panic(uint256)` reverts.
+pub fn decode_revert_reason(data: &[u8]) -> String {
+    if let Ok(error) = ISP1Verifier::ISP1VerifierErrors::abi_decode(data, true) {
+        return match error {
+            ISP1Verifier::ISP1VerifierErrors::InvalidProof(_) => {
+                "SP1 verifier: invalid proof".to_string()
+            }
+            ISP1Verifier::ISP1VerifierErrors::WrongVerifierSelector(e) => format!(
+                "SP1 verifier: wrong verifier selector, received: {}, expected: {}",
+                e.received, e.expected
+            ),
+        };
     }
+    alloy_sol_types::decode_revert_reason(data)
+        .unwrap_or_else(|| format!("unknown revert data: 0x{}", hex::encode(data)))
+}
+
+fn revert_or(revert_data: Option<Bytes>, tx_hash: Option<B256>, err: eyre::Report) -> eyre::Report {
+    match revert_data {
+        Some(data) => RevertError {
+            tx_hash,
+            reason: decode_revert_reason(&data),
+        }
+        .into(),
+        None => err,
+    }
+}
+
+/// Outcome of simulating a transaction against the latest state.
+#[derive(Debug, Clone)]
+pub enum Simulation {
+    Success { gas: u128, max_fee_per_gas: u128 },
+    Reverted { reason: String },
+}
+
+/// Transaction which was broadcast but not confirmed, kept so that retrying the same call
+/// replaces it (same nonce, bumped fees) rather than sending it again with the next nonce.
+#[derive(Debug, Clone)]
+struct PendingTx {
+    calldata: Bytes,
+    nonce: u64,
+    max_fee: u128,
+    priority_fee: u128,
+    // Hashes of all the versions sent, at most one of them can be included
+    sent: Vec<B256>,
+}
+
+pub struct ContractClient {
+    chain_id: u64,
+    provider: ReqwestProvider,
+    signer: Signer,
+    tx_config: TxConfig,
+    // Nonce following the last confirmed tx (if any) in case the node lags behind it
+    nonce: Mutex<Option<u64>>,
+    pending: Mutex<Option<PendingTx>>,
+    pub contract: Address,
 }
 
 impl ContractClient {
-    /// Creates a new `ContractClient`.
+    /// Creates a new `ContractClient` signing txs using the given private key.
     pub fn new(chain_id: u64, rpc_url: &str, private_key: &str, contract: &str) -> Result<Self> {
-        let provider = Provider::<Http>::try_from(rpc_url)?;
+        let signer = Signer::Local(private_key.parse()?);
+        Self::with_signer(chain_id, rpc_url, signer, contract)
+    }
 
-        let wallet: LocalWallet = private_key.parse::<LocalWallet>()?.with_chain_id(chain_id);
-        let client = SignerMiddleware::new(provider.clone(), wallet.clone());
+    /// Creates a new `ContractClient` using any of the supported signers.
+    pub fn with_signer(
+        chain_id: u64,
+        rpc_url: &str,
+        signer: Signer,
+        contract: &str,
+    ) -> Result<Self> {
+        let provider = ReqwestProvider::new_http(Url::parse(rpc_url)?);
         let contract = contract.parse::<Address>()?;
 
         Ok(ContractClient {
             chain_id,
-            client,
+            provider,
+            signer,
+            tx_config: TxConfig::from_env()?,
+            nonce: Mutex::new(None),
+            pending: Mutex::new(None),
             contract,
         })
     }
 
+    pub fn with_tx_config(mut self, tx_config: TxConfig) -> Self {
+        self.tx_config = tx_config;
+        self
+    }
+
+    /// Provider of the chain the contract is deployed on.
+    pub fn provider(&self) -> &ReqwestProvider {
+        &self.provider
+    }
+
+    /// Address of the account sending the transactions.
+    pub fn signer(&self) -> Address {
+        self.signer.address()
+    }
+
     /// Read data from the contract using calldata.
     pub async fn read(&self, calldata: Vec<u8>) -> Result<Vec<u8>> {
-        let mut tx = TypedTransaction::default();
-        tx.set_chain_id(self.chain_id);
-        tx.set_to(self.contract);
-        tx.set_data(calldata.into());
-        let data = self.client.call(&tx, None).await?;
+        let tx = TransactionRequest::default()
+            .with_to(self.contract)
+            .with_input(calldata);
+        let data = self.provider.call(&tx).await?;
 
         Ok(data.to_vec())
     }
 
-    /// Send a transaction with the given calldata.
-    pub async fn send(&self, calldata: Vec<u8>) -> Result<Option<TransactionReceipt>> {
-        let tx = TransactionRequest::new()
-            .chain_id(self.chain_id)
-            .to(self.contract)
-            .from(self.client.address())
-            .data(calldata);
+    /// Read data from the contract using one of it's typed calls (e.g. from `PoSVerifier`).
+    pub async fn call<C: SolCall>(&self, call: &C) -> Result<C::Return> {
+        let data = self.read(call.abi_encode()).await?;
+        Ok(C::abi_decode_returns(&data, true)?)
+    }
+
+    /// Simulates sending a transaction with the given calldata (using `eth_call` from the
+    /// signer) and estimates the gas it would use without broadcasting anything.
+    pub async fn simulate(&self, calldata: Vec<u8>) -> Result<Simulation> {
+        let tx = TransactionRequest::default()
+            .with_from(self.signer.address())
+            .with_to(self.contract)
+            .with_chain_id(self.chain_id)
+            .with_input(calldata);
+
+        if let Err(err) = self.provider.call(&tx).await {
+            return match err.as_error_resp().and_then(|e| e.as_revert_data()) {
+                Some(data) => Ok(Simulation::Reverted {
+                    reason: decode_revert_reason(&data),
+                }),
+                None => Err(err.into()),
+            };
+        }
+        let gas = self.provider.estimate_gas(&tx).await?;
+        let fees = self.provider.estimate_eip1559_fees(None).await?;
+
+        Ok(Simulation::Success {
+            gas,
+            max_fee_per_gas: fees.max_fee_per_gas,
+        })
+    }
+
+    /// Next nonce to be used i.e. the pending nonce of the signer unless the node didn't catch
+    /// up with the last tx confirmed by this client yet.
+    async fn next_nonce(&self) -> Result<u64> {
+        let pending = self
+            .provider
+            .get_transaction_count(self.signer.address())
+            .block_id(BlockId::pending())
+            .await?;
+        let confirmed = *self.nonce.lock().unwrap();
+        Ok(confirmed.map_or(pending, |nonce| nonce.max(pending)))
+    }
+
+    /// Send a transaction with the given calldata. The gas limit is estimated (which fails
+    /// early for reverting calls) and the fees are set as per EIP-1559. If the transaction
+    /// isn't confirmed within the timeout, it's replaced (same nonce) with bumped fees. If it
+    /// fails, the tx is kept pending so that sending the same calldata again keeps replacing
+    /// it instead of using a new nonce.
+    pub async fn send(&self, calldata: Vec<u8>) -> Result<TransactionReceipt> {
+        let calldata = Bytes::from(calldata);
+        let mut tx = TransactionRequest::default()
+            .with_from(self.signer.address())
+            .with_to(self.contract)
+            .with_chain_id(self.chain_id)
+            .with_input(calldata.clone());
+
+        // A previous attempt of the same call may have been included meanwhile, in which case
+        // the gas estimation would likely revert
+        let retry = self
+            .pending
+            .lock()
+            .unwrap()
+            .clone()
+            .filter(|pending| pending.calldata == calldata);
+        if let Some(pending) = retry.as_ref() {
+            if let Some(receipt) = self.find_receipt(&pending.sent).await? {
+                return self.confirmed(&tx, pending.nonce, receipt).await;
+            }
+        }
+
+        let gas_limit = match self.provider.estimate_gas(&tx).await {
+            Ok(gas_limit) => gas_limit,
+            Err(err) => {
+                let revert_data = err.as_error_resp().and_then(|e| e.as_revert_data());
+                return Err(revert_or(revert_data, None, err.into()));
+            }
+        };
+        let mut pending = match retry {
+            Some(pending) => {
+                info!(
+                    nonce = pending.nonce,
+                    sent = pending.sent.len(),
+                    "Replacing the tx of the previous attempt"
+                );
+                pending
+            }
+            None => PendingTx {
+                calldata,
+                nonce: self.next_nonce().await?,
+                max_fee: 0,
+                priority_fee: 0,
+                sent: Vec::new(),
+            },
+        };
+        let nonce = pending.nonce;
+        tx.set_gas_limit(gas_limit);
+        tx.set_nonce(nonce);
+
+        for bump in 0..=self.tx_config.max_fee_bumps {
+            // Use the current estimate unless the bumped fees are higher
+            let fees = self.provider.estimate_eip1559_fees(None).await?;
+            let percent = 100 + self.tx_config.fee_bump_percent;
+            pending.max_fee = fees.max_fee_per_gas.max(pending.max_fee * percent / 100);
+            pending.priority_fee = fees
+                .max_priority_fee_per_gas
+                .max(pending.priority_fee * percent / 100);
+            tx.set_max_fee_per_gas(pending.max_fee);
+            tx.set_max_priority_fee_per_gas(pending.priority_fee);
+            if !pending.sent.is_empty() {
+                info!(
+                    nonce,
+                    bump,
+                    max_fee = pending.max_fee,
+                    priority_fee = pending.priority_fee,
+                    "Replacing tx with bumped fees"
+                );
+            }
+
+            let raw = self.signer.sign_transaction(&tx).await?;
+            let sent = match self.provider.send_raw_transaction(&raw).await {
+                Ok(sent) => sent,
+                Err(err) => {
+                    // The replacement fails if one of the earlier txs got included meanwhile
+                    if let Some(receipt) = self.find_receipt(&pending.sent).await? {
+                        return self.confirmed(&tx, nonce, receipt).await;
+                    }
+                    self.keep_pending(pending).await?;
+                    return Err(err.into());
+                }
+            };
+
+            let tx_hash = *sent.tx_hash();
+            pending.sent.push(tx_hash);
+            *self.pending.lock().unwrap() = Some(pending.clone());
+            info!(%tx_hash, nonce, "Sent tx");
+            let result = sent
+                .with_required_confirmations(self.tx_config.confirmations)
+                .with_timeout(Some(self.tx_config.timeout))
+                .get_receipt()
+                .await;
+            match result {
+                Ok(receipt) => return self.confirmed(&tx, nonce, receipt).await,
+                Err(err) => {
+                    warn!(%tx_hash, %err, "Tx not confirmed");
+                    if let Some(receipt) = self.find_receipt(&pending.sent).await? {
+                        return self.confirmed(&tx, nonce, receipt).await;
+                    }
+                }
+            }
+        }
+
+        let sent = pending.sent.clone();
+        self.keep_pending(pending).await?;
+        eyre::bail!(
+            "tx with nonce {} not confirmed after {} fee bumps, sent: {:?}",
+            nonce,
+            self.tx_config.max_fee_bumps,
+            sent
+        )
+    }
+
+    /// Keeps the tx to be replaced by the next attempt, unless its nonce was used by another
+    /// tx (of the same signer) meanwhile in which case the next attempt uses a new nonce.
+    async fn keep_pending(&self, pending: PendingTx) -> Result<()> {
+        let confirmed = self
+            .provider
+            .get_transaction_count(self.signer.address())
+            .block_id(BlockId::latest())
+            .await?;
+        let keep = (confirmed <= pending.nonce).then_some(pending);
+        *self.pending.lock().unwrap() = keep;
+        Ok(())
+    }
+
+    /// Advances the nonce past the confirmed tx and checks its receipt.
+    async fn confirmed(
+        &self,
+        tx: &TransactionRequest,
+        nonce: u64,
+        receipt: TransactionReceipt,
+    ) -> Result<TransactionReceipt> {
+        *self.nonce.lock().unwrap() = Some(nonce + 1);
+        *self.pending.lock().unwrap() = None;
+        self.check_receipt(tx, receipt).await
+    }
+
+    /// Send a transaction for one of the typed calls of the contract.
+    pub async fn send_call<C: SolCall>(&self, call: &C) -> Result<TransactionReceipt> {
+        self.send(call.abi_encode()).await
+    }
+
+    /// Returns the receipt of any of the given txs (all of them share the same nonce, so at
+    /// most one of them can be included).
+    async fn find_receipt(&self, tx_hashes: &[B256]) -> Result<Option<TransactionReceipt>> {
+        for tx_hash in tx_hashes.iter() {
+            if let Some(receipt) = self.provider.get_transaction_receipt(*tx_hash).await? {
+                return Ok(Some(receipt));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Checks the status of the receipt and decodes the revert reason if it failed, by replaying
+    /// the call on the state before the block it was included in (after it, the state the call
+    /// depends on e.g. the last verified block may have been changed by another tx).
+    async fn check_receipt(
+        &self,
+        tx: &TransactionRequest,
+        receipt: TransactionReceipt,
+    ) -> Result<TransactionReceipt> {
+        if receipt.status() {
+            return Ok(receipt);
+        }
+
+        let tx_hash = Some(receipt.transaction_hash);
+        let mut call = tx.clone();
+        call.nonce = None;
+        let block_id = receipt
+            .block_number
+            .map(|number| BlockId::from(number.saturating_sub(1)))
+            .unwrap_or_else(BlockId::latest);
+        let err = match self.provider.call(&call).block(block_id).await {
+            Ok(_) => {
+                return Err(RevertError {
+                    tx_hash,
+                    reason: "unknown".to_string(),
+                }
+                .into())
+            }
+            Err(err) => err,
+        };
+        let revert_data = err.as_error_resp().and_then(|e| e.as_revert_data());
+        let fallback = RevertError {
+            tx_hash,
+            reason: err.to_string(),
+        };
+        Err(revert_or(revert_data, tx_hash, fallback.into()))
+    }
+
+    /// Decodes the checkpoints verified by the contract from the logs of the receipt.
+    pub fn verified_checkpoints(
+        &self,
+        receipt: &TransactionReceipt,
+    ) -> Vec<PoSVerifier::CheckpointVerified> {
+        receipt
+            .inner
+            .logs()
+            .iter()
+            .filter(|log| log.inner.address == self.contract)
+            .filter_map(|log| {
+                PoSVerifier::CheckpointVerified::decode_log_data(&log.inner.data, true).ok()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use alloy_consensus::{TxEip1559, TxEnvelope};
+    use alloy_eips::eip2718::Decodable2718;
+    use alloy_signer_local::PrivateKeySigner;
+    use alloy_sol_types::{Revert, SolError};
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    pub(crate) const BASE_FEE: u128 = 1_000;
+    // Priority fee estimated from the fee history
+    pub(crate) const PRIORITY_FEE: u128 = 100;
+
+    /// State of a chain faked by `FakeRpc`. Txs are included once their receipt is requested
+    /// if they have the next nonce and pay at least the minimum priority fee.
+    #[derive(Debug, Default)]
+    pub(crate) struct FakeChain {
+        /// Nonce of the next tx to be included.
+        pub nonce: u64,
+        pub min_priority_fee: u128,
+        /// Revert reason of the calls, if they revert.
+        pub revert: Option<String>,
+        /// Reverts only on-chain, the gas estimation succeeds.
+        pub revert_on_chain: bool,
+        /// Error returned when sending raw txs (e.g. the node being down).
+        pub send_error: Option<String>,
+        pub sent: Vec<(B256, TxEip1559)>,
+        pub included: Vec<B256>,
+        /// Block ids of the `eth_call`s.
+        pub calls: Vec<Value>,
+        /// Results of the `eth_call`s by calldata, other calls return empty data.
+        pub call_results: Vec<(Bytes, Bytes)>,
+        /// Hash returned for any block requested by number.
+        pub block_hash: B256,
+    }
+
+    impl FakeChain {
+        fn pending_nonce(&self) -> u64 {
+            self.sent
+                .iter()
+                .map(|(_, tx)| tx.nonce + 1)
+                .fold(self.nonce, u64::max)
+        }
+
+        fn receipt(&mut self, tx_hash: B256) -> Value {
+            let Some((_, tx)) = self.sent.iter().find(|(hash, _)| *hash == tx_hash) else {
+                return Value::Null;
+            };
+            if !self.included.contains(&tx_hash) {
+                if tx.nonce != self.nonce || tx.max_priority_fee_per_gas < self.min_priority_fee {
+                    return Value::Null;
+                }
+                self.nonce += 1;
+                self.included.push(tx_hash);
+            }
+            let status = self.revert.is_none() || !self.revert_on_chain;
+            json!({
+                "transactionHash": tx_hash,
+                "transactionIndex": "0x0",
+                "blockHash": B256::with_last_byte(2),
+                "blockNumber": "0x2",
+                "from": Address::ZERO,
+                "to": tx.to.to(),
+                "cumulativeGasUsed": "0x5208",
+                "gasUsed": "0x5208",
+                "effectiveGasPrice": format!("{:#x}", BASE_FEE + tx.max_priority_fee_per_gas),
+                "contractAddress": null,
+                "logs": [],
+                "logsBloom": format!("0x{}", "00".repeat(256)),
+                "status": if status { "0x1" } else { "0x0" },
+                "type": "0x2",
+            })
+        }
+
+        fn block(&self, number: &Value) -> Value {
+            json!({
+                "hash": self.block_hash,
+                "parentHash": B256::ZERO,
+                "sha3Uncles": B256::ZERO,
+                "miner": Address::ZERO,
+                "stateRoot": B256::ZERO,
+                "transactionsRoot": B256::ZERO,
+                "receiptsRoot": B256::ZERO,
+                "logsBloom": format!("0x{}", "00".repeat(256)),
+                "difficulty": "0x0",
+                "number": number,
+                "gasLimit": "0x1c9c380",
+                "gasUsed": "0x0",
+                "timestamp": "0x0",
+                "extraData": "0x",
+                "mixHash": B256::ZERO,
+                "nonce": "0x0000000000000000",
+                "baseFeePerGas": format!("{:#x}", BASE_FEE),
+                "uncles": [],
+                "transactions": [],
+            })
+        }
+
+        fn revert_error(&self) -> Value {
+            let reason = self.revert.clone().unwrap_or_default();
+            json!({
+                "code": 3,
+                "message": format!("execution reverted: {}", reason),
+                "data": Bytes::from(Revert { reason }.abi_encode()),
+            })
+        }
+
+        fn handle(&mut self, method: &str, params: &Value) -> Result<Value, Value> {
+            match method {
+                "eth_chainId" => Ok(json!("0x1")),
+                "eth_blockNumber" => Ok(json!("0x2")),
+                "eth_estimateGas" if self.revert.is_some() && !self.revert_on_chain => {
+                    Err(self.revert_error())
+                }
+                "eth_estimateGas" => Ok(json!("0x5208")),
+                "eth_call" => {
+                    self.calls.push(params[1].clone());
+                    let input = params[0].get("input").or_else(|| params[0].get("data"));
+                    let input: Option<Bytes> =
+                        input.and_then(|v| serde_json::from_value(v.clone()).ok());
+                    let result = self
+                        .call_results
+                        .iter()
+                        .find(|(calldata, _)| Some(calldata) == input.as_ref())
+                        .map(|(_, result)| result.clone())
+                        .unwrap_or_default();
+                    match self.revert {
+                        Some(_) => Err(self.revert_error()),
+                        None => Ok(json!(result)),
+                    }
+                }
+                "eth_getTransactionCount" => {
+                    let nonce = match params[1].as_str() {
+                        Some("pending") => self.pending_nonce(),
+                        _ => self.nonce,
+                    };
+                    Ok(json!(format!("{:#x}", nonce)))
+                }
+                "eth_feeHistory" => Ok(json!({
+                    "oldestBlock": "0x1",
+                    "baseFeePerGas": [format!("{:#x}", BASE_FEE), format!("{:#x}", BASE_FEE)],
+                    "gasUsedRatio": [0.5],
+                    "reward": [[format!("{:#x}", PRIORITY_FEE)]],
+                })),
+                "eth_sendRawTransaction" => {
+                    if let Some(message) = self.send_error.as_ref() {
+                        return Err(json!({ "code": -32000, "message": message }));
+                    }
+                    let raw: Bytes = serde_json::from_value(params[0].clone()).unwrap();
+                    let TxEnvelope::Eip1559(signed) =
+                        TxEnvelope::decode_2718(&mut raw.as_ref()).unwrap()
+                    else {
+                        panic!("expected an EIP-1559 tx");
+                    };
+                    if signed.tx().nonce < self.nonce {
+                        return Err(json!({ "code": -32000, "message": "nonce too low" }));
+                    }
+                    let tx_hash = *signed.hash();
+                    self.sent.push((tx_hash, signed.tx().clone()));
+                    Ok(json!(tx_hash))
+                }
+                "eth_getTransactionReceipt" => {
+                    let tx_hash: B256 = serde_json::from_value(params[0].clone()).unwrap();
+                    Ok(self.receipt(tx_hash))
+                }
+                "eth_getBlockByNumber" => Ok(self.block(&params[0])),
+                // E.g. the blocks polled for confirming txs, which are confirmed by their
+                // receipts only
+                _ => Ok(Value::Null),
+            }
+        }
+    }
+
+    /// Local stand-in for the json rpc of a chain.
+    pub(crate) struct FakeRpc {
+        pub url: String,
+        pub chain: Arc<Mutex<FakeChain>>,
+    }
+
+    impl FakeRpc {
+        pub(crate) async fn start(chain: FakeChain) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let chain = Arc::new(Mutex::new(chain));
+            let state = chain.clone();
+            tokio::spawn(async move {
+                loop {
+                    let (stream, _) = listener.accept().await.unwrap();
+                    tokio::spawn(Self::respond(stream, state.clone()));
+                }
+            });
+            Self { url, chain }
+        }
+
+        async fn respond(mut stream: TcpStream, chain: Arc<Mutex<FakeChain>>) {
+            let mut buf = Vec::new();
+            let body = loop {
+                let mut chunk = [0u8; 4096];
+                let n = stream.read(&mut chunk).await.unwrap();
+                if n == 0 {
+                    return;
+                }
+                buf.extend_from_slice(&chunk[..n]);
+                let request = String::from_utf8_lossy(&buf).to_string();
+                if let Some((head, body)) = request.split_once("\r\n\r\n") {
+                    let length: usize = head
+                        .lines()
+                        .find_map(|line| {
+                            line.to_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|l| l.trim().parse().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break body.to_string();
+                    }
+                }
+            };
+
+            let request: Value = serde_json::from_str(&body).unwrap();
+            let method = request["method"].as_str().unwrap();
+            let result = chain.lock().unwrap().handle(method, &request["params"]);
+            let response = match result {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+                Err(error) => json!({ "jsonrpc": "2.0", "id": request["id"], "error": error }),
+            }
+            .to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{}",
+                response.len(),
+                response
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+
+        pub(crate) fn client(&self, max_fee_bumps: u32) -> ContractClient {
+            let signer = Signer::Local(PrivateKeySigner::random());
+            let contract = Address::with_last_byte(10).to_string();
+            ContractClient::with_signer(1, &self.url, signer, &contract)
+                .unwrap()
+                .with_tx_config(TxConfig {
+                    confirmations: 1,
+                    timeout: Duration::from_secs(1),
+                    max_fee_bumps,
+                    fee_bump_percent: 20,
+                })
+        }
+
+        pub(crate) fn sent(&self) -> Vec<(B256, TxEip1559)> {
+            self.chain.lock().unwrap().sent.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_replaces_stuck_tx() {
+        // Only included once the fees are bumped
+        let rpc = FakeRpc::start(FakeChain {
+            min_priority_fee: PRIORITY_FEE + 1,
+            ..Default::default()
+        })
+        .await;
+        let receipt = rpc.client(2).send(vec![1]).await.unwrap();
+
+        let sent = rpc.sent();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(receipt.transaction_hash, sent[1].0);
+        assert!(sent.iter().all(|(_, tx)| tx.nonce == 0));
+        assert_eq!(sent[0].1.max_priority_fee_per_gas, PRIORITY_FEE);
+        assert_eq!(sent[1].1.max_priority_fee_per_gas, PRIORITY_FEE * 120 / 100);
+        assert_eq!(
+            sent[1].1.max_fee_per_gas,
+            sent[0].1.max_fee_per_gas * 120 / 100
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_retry_replaces_pending_tx() {
+        let rpc = FakeRpc::start(FakeChain {
+            min_priority_fee: u128::MAX,
+            ..Default::default()
+        })
+        .await;
+        let client = rpc.client(0);
+        let err = client.send(vec![1]).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("tx with nonce 0 not confirmed after 0 fee bumps"));
+
+        // The retry replaces the pending tx (with bumped fees) even though the node already
+        // counts it's nonce as used
+        rpc.chain.lock().unwrap().min_priority_fee = PRIORITY_FEE + 1;
+        let receipt = client.send(vec![1]).await.unwrap();
+        let sent = rpc.sent();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(receipt.transaction_hash, sent[1].0);
+        assert_eq!(sent[1].1.nonce, 0);
+        assert_eq!(sent[1].1.max_priority_fee_per_gas, PRIORITY_FEE * 120 / 100);
 
-        let tx = self.client.send_transaction(tx, None).await?.await?;
+        // Another call uses the next nonce
+        client.send(vec![2]).await.unwrap();
+        assert_eq!(rpc.sent()[2].1.nonce, 1);
+    }
+
+    #[tokio::test]
+    async fn test_send_retry_after_nonce_used() {
+        let rpc = FakeRpc::start(FakeChain {
+            min_priority_fee: u128::MAX,
+            ..Default::default()
+        })
+        .await;
+        let client = rpc.client(0);
+        client.send(vec![1]).await.unwrap_err();
+
+        // Another tx of the signer (e.g. sent by hand) took the nonce meanwhile, so the retry
+        // can't replace the pending tx anymore and has to use a new nonce
+        {
+            let mut chain = rpc.chain.lock().unwrap();
+            chain.nonce = 1;
+            chain.sent.clear();
+            chain.min_priority_fee = 0;
+        }
+        client.send(vec![1]).await.unwrap_err();
+        client.send(vec![1]).await.unwrap();
+        let sent = rpc.sent();
+        assert!(sent.iter().all(|(_, tx)| tx.nonce == 1), "{:?}", sent);
+    }
+
+    #[tokio::test]
+    async fn test_send_revert_reason() {
+        let rpc = FakeRpc::start(FakeChain {
+            revert: Some("checkpoint already verified".to_string()),
+            revert_on_chain: true,
+            ..Default::default()
+        })
+        .await;
+        let err = rpc.client(0).send(vec![1]).await.unwrap_err();
+        let err = err.downcast_ref::<RevertError>().unwrap();
+        assert_eq!(err.tx_hash, Some(rpc.sent()[0].0));
+        assert_eq!(err.reason, "revert: checkpoint already verified");
 
-        Ok(tx)
+        // Replayed on the state before the block of the tx
+        assert_eq!(rpc.chain.lock().unwrap().calls, vec![json!("0x1")]);
     }
 }
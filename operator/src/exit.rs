@@ -0,0 +1,280 @@
+use crate::contract::ContractClient;
+use crate::heimdall::HeimdallSource;
+use crate::sources::BorSource;
+
+use alloy_primitives::{Address, Bytes, B256};
+use alloy_provider::{Provider, ReqwestProvider};
+use alloy_rpc_types::BlockNumberOrTag;
+use checkpoint_proof::exit::{
+    block_leaf, checkpoint_branch, checkpoint_root, encode_receipt, receipt_proof, Receipt,
+    ReceiptLog,
+};
+use eyre::Result;
+use tracing::{info, info_span, Instrument};
+use zk_checkpoint_lib::exit::ExitProof;
+use zk_checkpoint_lib::PoSVerifier;
+
+/// Builds the proof of the receipt of a bor tx (and the log at `log_index`) against the root
+/// hash of the checkpoint including it's block, which is searched for if not given.
+pub async fn generate_exit_proof(
+    heimdall: &dyn HeimdallSource,
+    bor: &dyn BorSource,
+    provider: &ReqwestProvider,
+    tx_hash: B256,
+    log_index: u64,
+    checkpoint_id: Option<u64>,
+) -> Result<ExitProof> {
+    // Fetch the receipt of the transaction to find it's block
+    let receipt = provider
+        .get_transaction_receipt(tx_hash)
+        .await?
+        .ok_or_else(|| eyre::eyre!("receipt not found for tx: {}", tx_hash))?;
+    let block_number = receipt
+        .block_number
+        .ok_or_else(|| eyre::eyre!("tx {} is pending", tx_hash))?;
+
+    // Find the checkpoint which contains the block
+    let checkpoint_id = match checkpoint_id {
+        Some(id) => id,
+        None => heimdall
+            .find_checkpoint_by_block(block_number)
+            .await?
+            .ok_or_else(|| eyre::eyre!("bor block {} not checkpointed yet", block_number))?,
+    };
+    let checkpoint = heimdall.fetch_checkpoint_by_id(checkpoint_id).await?.result;
+    info!(
+        block_number,
+        checkpoint_id,
+        start_block = checkpoint.start_block,
+        end_block = checkpoint.end_block,
+        "Found bor block in checkpoint"
+    );
+    let block_index = block_number
+        .checked_sub(checkpoint.start_block)
+        .filter(|_| block_number <= checkpoint.end_block)
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "bor block {} not in checkpoint {} ({} to {})",
+                block_number,
+                checkpoint_id,
+                checkpoint.start_block,
+                checkpoint.end_block
+            )
+        })? as usize;
+
+    // Rebuild the checkpoint root hash from the bor headers
+    let headers = bor
+        .headers(checkpoint.start_block, checkpoint.end_block)
+        .instrument(info_span!("bor_headers"))
+        .await?;
+    let leaves: Vec<B256> = headers
+        .iter()
+        .map(|header| {
+            block_leaf(
+                header.number,
+                header.timestamp,
+                header.transactions_root,
+                header.receipts_root,
+            )
+        })
+        .collect();
+    let root_hash = checkpoint.root_hash;
+    if checkpoint_root(&leaves) != root_hash {
+        eyre::bail!(
+            "checkpoint root hash mismatch, expected: {}, got: {}",
+            root_hash,
+            checkpoint_root(&leaves)
+        );
+    }
+    let block = &headers[block_index];
+
+    // Rebuild the receipt trie of the block excluding the state sync receipt (which bor
+    // appends to the block receipts but doesn't include in the receipts root). The receipt is
+    // looked up by it's tx hash as it's index is the one among the remaining receipts.
+    let receipts = provider
+        .get_block_receipts(BlockNumberOrTag::Number(block_number))
+        .await?
+        .ok_or_else(|| eyre::eyre!("receipts not found for bor block {}", block_number))?;
+    let receipts: Vec<_> = receipts
+        .iter()
+        .filter(|r| !(r.from == Address::ZERO && r.to == Some(Address::ZERO)))
+        .collect();
+    let tx_index = receipts
+        .iter()
+        .position(|r| r.transaction_hash == tx_hash)
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "receipt of tx {} not found in bor block {}",
+                tx_hash,
+                block_number
+            )
+        })?;
+    let receipts: Vec<(Receipt, Vec<u8>)> = receipts
+        .iter()
+        .map(|r| {
+            let receipt = Receipt {
+                status: r.inner.status() as u64,
+                cumulative_gas_used: r.inner.cumulative_gas_used() as u64,
+                logs_bloom: *r.inner.logs_bloom(),
+                logs: r
+                    .inner
+                    .logs()
+                    .iter()
+                    .map(|log| ReceiptLog {
+                        address: log.inner.address,
+                        topics: log.inner.data.topics().to_vec(),
+                        data: log.inner.data.data.clone(),
+                    })
+                    .collect(),
+            };
+            let encoded = encode_receipt(r.inner.tx_type() as u8, &receipt);
+            (receipt, encoded)
+        })
+        .collect();
+    let encoded: Vec<Vec<u8>> = receipts
+        .iter()
+        .map(|(_, encoded)| encoded.clone())
+        .collect();
+    let (receipts_root, receipt_proof) = receipt_proof(&encoded, tx_index as u64);
+    if receipts_root != block.receipts_root {
+        eyre::bail!(
+            "receipts root mismatch for bor block {}, expected: {}, got: {}",
+            block_number,
+            block.receipts_root,
+            receipts_root
+        );
+    }
+    let (receipt, encoded) = &receipts[tx_index];
+    let log = receipt.logs.get(log_index as usize).ok_or_else(|| {
+        eyre::eyre!(
+            "log {} not found in the receipt of tx {} ({} logs)",
+            log_index,
+            tx_hash,
+            receipt.logs.len()
+        )
+    })?;
+
+    info!(log_index, address = %log.address, "Found log");
+
+    Ok(ExitProof {
+        checkpoint_id,
+        root_hash,
+        start_block: checkpoint.start_block,
+        end_block: checkpoint.end_block,
+        block_number,
+        timestamp: block.timestamp,
+        transactions_root: block.transactions_root,
+        receipts_root,
+        block_proof: checkpoint_branch(&leaves, block_index),
+        tx_index: tx_index as u64,
+        receipt: Bytes::from(encoded.clone()),
+        receipt_proof,
+        log_index,
+    })
+}
+
+/// Checks the exit proof can be verified against the verifier, i.e. it's block is verified and
+/// it's checkpoint root hash was committed by a verified proof.
+pub async fn check_verified(verifier: &ContractClient, proof: &ExitProof) -> Result<()> {
+    let last_verified: u64 = verifier
+        .call(&PoSVerifier::lastVerifiedBorBlockNumberCall {})
+        .await?
+        ._0
+        .to();
+    if proof.block_number > last_verified {
+        eyre::bail!(
+            "bor block {} not verified yet, last verified: {}",
+            proof.block_number,
+            last_verified
+        );
+    }
+    let verified = verifier
+        .call(&PoSVerifier::verifiedRootHashesCall {
+            _0: proof.root_hash,
+        })
+        .await?
+        ._0;
+    if !verified {
+        eyre::bail!(
+            "root hash {} of checkpoint {} not verified",
+            proof.root_hash,
+            proof.checkpoint_id
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::tests::{FakeChain, FakeRpc};
+    use crate::signer::Signer;
+    use alloy_primitives::U256;
+    use alloy_sol_types::{SolCall, SolValue};
+
+    fn proof(block_number: u64, root_hash: B256) -> ExitProof {
+        ExitProof {
+            checkpoint_id: 7,
+            root_hash,
+            start_block: 100,
+            end_block: 200,
+            block_number,
+            timestamp: 0,
+            transactions_root: B256::ZERO,
+            receipts_root: B256::ZERO,
+            block_proof: vec![],
+            tx_index: 0,
+            receipt: Bytes::new(),
+            receipt_proof: vec![],
+            log_index: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_verified() {
+        let verified_root = B256::repeat_byte(1);
+        let chain = FakeChain {
+            call_results: vec![
+                (
+                    PoSVerifier::lastVerifiedBorBlockNumberCall {}
+                        .abi_encode()
+                        .into(),
+                    U256::from(200).abi_encode().into(),
+                ),
+                (
+                    PoSVerifier::verifiedRootHashesCall { _0: verified_root }
+                        .abi_encode()
+                        .into(),
+                    true.abi_encode().into(),
+                ),
+            ],
+            ..Default::default()
+        };
+        let rpc = FakeRpc::start(chain).await;
+        let verifier = ContractClient::with_signer(
+            1,
+            &rpc.url,
+            Signer::ReadOnly(Address::ZERO),
+            &Address::with_last_byte(1).to_string(),
+        )
+        .unwrap();
+
+        check_verified(&verifier, &proof(150, verified_root))
+            .await
+            .unwrap();
+
+        let err = check_verified(&verifier, &proof(201, verified_root))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "bor block 201 not verified yet, last verified: 200"
+        );
+
+        // The root hash of the heimdall checkpoint differs from the verified one
+        let err = check_verified(&verifier, &proof(150, B256::repeat_byte(2)))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("of checkpoint 7 not verified"));
+    }
+}
@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use eyre::Result;
+
+use crate::types::{
+    BlockResponse, CheckpointResponse, MilestoneResponse, SpanResponse, TxResponse,
+};
+
+/// Source of heimdall data (checkpoints, txs, blocks, spans and milestones) i.e. the rest api
+/// and tendermint rpc (`PoSClient`) of a node, or a fake in the tests.
+#[async_trait]
+pub trait HeimdallSource: Send + Sync {
+    async fn fetch_checkpoint_by_id(&self, id: u64) -> Result<CheckpointResponse>;
+
+    async fn fetch_checkpoint_count(&self) -> Result<u64>;
+
+    async fn fetch_tx_by_hash(&self, hash: String) -> Result<TxResponse>;
+
+    async fn fetch_block_by_number(&self, number: u64) -> Result<BlockResponse>;
+
+    async fn fetch_span_by_id(&self, id: u64) -> Result<SpanResponse>;
+
+    async fn fetch_latest_span(&self) -> Result<SpanResponse>;
+
+    async fn fetch_milestone_by_number(&self, number: u64) -> Result<MilestoneResponse>;
+
+    async fn fetch_latest_milestone(&self) -> Result<MilestoneResponse>;
+
+    /// Fetch the id of the checkpoint which contains the given bor block (if any) by binary
+    /// searching over all checkpoints.
+    async fn find_checkpoint_by_block(&self, number: u64) -> Result<Option<u64>> {
+        let (mut low, mut high) = (1, self.fetch_checkpoint_count().await?);
+        while low <= high {
+            let id = low + (high - low) / 2;
+            let checkpoint = self.fetch_checkpoint_by_id(id).await?.result;
+            if number < checkpoint.start_block {
+                high = id - 1;
+            } else if number > checkpoint.end_block {
+                low = id + 1;
+            } else {
+                return Ok(Some(id));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Fetch the span which contains the given bor block by walking back from the latest span.
+    async fn fetch_span_by_block(&self, number: u64) -> Result<SpanResponse> {
+        let mut span = self.fetch_latest_span().await?;
+        while span.result.start_block > number && span.result.span_id > 0 {
+            span = self.fetch_span_by_id(span.result.span_id - 1).await?;
+        }
+        if span.result.start_block > number || span.result.end_block < number {
+            eyre::bail!("no span found for bor block {}", number);
+        }
+        Ok(span)
+    }
+}
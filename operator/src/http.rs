@@ -0,0 +1,337 @@
+use eyre::Result;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+use std::{fmt, time::Duration};
+use tracing::warn;
+
+use crate::config::env_var;
+use crate::metrics::fetch_error;
+
+/// User agent sent with all requests so that endpoint operators can identify the traffic.
+pub const USER_AGENT: &str = concat!("zk-checkpoint/", env!("CARGO_PKG_VERSION"));
+
+// Error bodies are truncated so that html error pages (e.g. from a load balancer) don't flood
+// the logs
+const MAX_ERROR_BODY_LEN: usize = 1024;
+
+/// Timeouts and retry policy of the http requests (to heimdall and tendermint).
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    /// Timeout of a single request including reading the response.
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    /// Number of times a request is retried on the same endpoint before falling back to the
+    /// next one (if any).
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled for every retry after.
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(10),
+            max_retries: 3,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Reads the config from the `HTTP_TIMEOUT`, `HTTP_CONNECT_TIMEOUT` (in seconds),
+    /// `HTTP_MAX_RETRIES` and `HTTP_BACKOFF_MS` env vars falling back to the defaults.
+    pub fn from_env() -> Result<Self> {
+        let mut config = Self::default();
+        if let Some(value) = env_var("HTTP_TIMEOUT") {
+            config.timeout = Duration::from_secs(value.trim().parse()?);
+        }
+        if let Some(value) = env_var("HTTP_CONNECT_TIMEOUT") {
+            config.connect_timeout = Duration::from_secs(value.trim().parse()?);
+        }
+        if let Some(value) = env_var("HTTP_MAX_RETRIES") {
+            config.max_retries = value.trim().parse()?;
+        }
+        if let Some(value) = env_var("HTTP_BACKOFF_MS") {
+            config.backoff = Duration::from_millis(value.trim().parse()?);
+        }
+        Ok(config)
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+/// Error of a failed request with the url and (if any) the status and body of the response,
+/// so that the error payloads of heimdall aren't lost.
+#[derive(Debug, Clone)]
+pub struct HttpError {
+    pub url: String,
+    pub status: Option<StatusCode>,
+    pub body: Option<String>,
+    pub reason: String,
+    /// Delay requested by the server using the `Retry-After` header.
+    pub retry_after: Option<Duration>,
+}
+
+impl HttpError {
+    fn new(url: &str, reason: String) -> Self {
+        Self {
+            url: url.to_string(),
+            status: None,
+            body: None,
+            reason,
+            retry_after: None,
+        }
+    }
+
+    /// Transport errors (e.g. timeouts), rate limits and server errors are worth retrying,
+    /// other errors would fail again with the same request.
+    pub fn is_retryable(&self) -> bool {
+        match self.status {
+            None => true,
+            Some(status) => status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error(),
+        }
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request to {} failed", self.url)?;
+        if let Some(status) = self.status {
+            write!(f, " with status {}", status)?;
+        }
+        write!(f, ": {}", self.reason)?;
+        if let Some(body) = self.body.as_ref() {
+            write!(f, ", body: {}", body)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+fn truncate(body: String) -> String {
+    if body.len() <= MAX_ERROR_BODY_LEN {
+        return body;
+    }
+    let truncated: String = body.chars().take(MAX_ERROR_BODY_LEN).collect();
+    format!("{}...", truncated)
+}
+
+/// Http client fetching json from a list of equivalent endpoints, retrying each of them with
+/// exponential backoff before falling back to the next one.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: Client,
+    config: HttpConfig,
+    // Source the failed requests are counted for in the metrics (e.g. heimdall)
+    source: &'static str,
+}
+
+impl HttpClient {
+    pub fn new(source: &'static str, config: HttpConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(config.timeout)
+            .connect_timeout(config.connect_timeout)
+            .user_agent(USER_AGENT)
+            .build()?;
+        Ok(Self {
+            client,
+            config,
+            source,
+        })
+    }
+
+    /// Fetches the path from the first endpoint which returns a valid response. Errors which
+    /// aren't retryable (e.g. a 404 or an invalid response) aren't retried on the same endpoint
+    /// but the next one is still tried, as an endpoint may lag behind or be misconfigured (e.g.
+    /// serve an html page). Fails with the error of the last endpoint if all of them fail.
+    pub async fn get_json<T: DeserializeOwned>(
+        &self,
+        endpoints: &[String],
+        path: &str,
+    ) -> Result<T> {
+        let mut last_err = None;
+        for endpoint in endpoints {
+            let url = format!("{}{}", endpoint, path);
+            match self.get_with_retries(&url).await {
+                Ok(response) => return Ok(response),
+                Err(err) => {
+                    warn!(%err, "Request failed, trying the next endpoint (if any)");
+                    last_err = Some(err);
+                }
+            }
+        }
+        match last_err {
+            Some(err) => Err(err.into()),
+            None => eyre::bail!("no endpoints configured for {}", path),
+        }
+    }
+
+    async fn get_with_retries<T: DeserializeOwned>(&self, url: &str) -> Result<T, HttpError> {
+        let mut attempt = 0;
+        loop {
+            match self.get(url).await.inspect_err(fetch_error(self.source)) {
+                Ok(response) => return Ok(response),
+                Err(err) if err.is_retryable() && attempt < self.config.max_retries => {
+                    let backoff = err
+                        .retry_after
+                        .unwrap_or_else(|| self.config.backoff(attempt))
+                        .min(self.config.max_backoff);
+                    warn!(%err, ?backoff, "Request failed, retrying");
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, url: &str) -> Result<T, HttpError> {
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|err| HttpError::new(url, err.to_string()))?;
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .map(Duration::from_secs);
+        let body = response
+            .text()
+            .await
+            .map_err(|err| HttpError::new(url, err.to_string()))?;
+
+        let error = |reason: String| HttpError {
+            url: url.to_string(),
+            status: Some(status),
+            body: Some(truncate(body.clone())),
+            reason,
+            retry_after,
+        };
+        if !status.is_success() {
+            return Err(error("unexpected status".to_string()));
+        }
+        serde_json::from_str(&body).map_err(|err| error(format!("invalid response: {}", err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Local stand-in for an endpoint which answers each request (on a new connection) with
+    /// the next of the given status and body.
+    async fn serve(listener: TcpListener, responses: Vec<(u16, &'static str)>) {
+        for (status, body) in responses {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = Vec::new();
+            while !String::from_utf8_lossy(&buf).contains("\r\n\r\n") {
+                let mut chunk = [0u8; 1024];
+                let n = stream.read(&mut chunk).await.unwrap();
+                buf.extend_from_slice(&chunk[..n]);
+            }
+            let response = format!(
+                "HTTP/1.1 {} X\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    }
+
+    async fn endpoint(responses: Vec<(u16, &'static str)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, responses));
+        url
+    }
+
+    fn client(max_retries: u32) -> HttpClient {
+        HttpClient::new(
+            "http-test",
+            HttpConfig {
+                max_retries,
+                backoff: Duration::from_millis(1),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_retry_and_fallback() {
+        // The first endpoint keeps failing, the second one recovers after a rate limit
+        let failing = endpoint(vec![(503, "unavailable"), (502, "bad gateway")]).await;
+        let fallback = endpoint(vec![(429, "slow down"), (200, r#"{"result":1}"#)]).await;
+
+        let response: Value = client(1)
+            .get_json(&[failing, fallback], "/checkpoints/count")
+            .await
+            .unwrap();
+        assert_eq!(response["result"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_on_not_found_and_invalid_response() {
+        // E.g. an endpoint lagging behind and one serving an html page
+        let lagging = endpoint(vec![(404, r#"{"error":"checkpoint not found"}"#)]).await;
+        let html = endpoint(vec![(200, "<html>maintenance</html>")]).await;
+        let fallback = endpoint(vec![(200, r#"{"result":1}"#)]).await;
+
+        let response: Value = client(3)
+            .get_json(&[lagging, html, fallback], "/checkpoints/1")
+            .await
+            .unwrap();
+        assert_eq!(response["result"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_all_endpoints_fail() {
+        let lagging = endpoint(vec![(404, r#"{"error":"checkpoint not found"}"#)]).await;
+        let html = endpoint(vec![(200, "<html>maintenance</html>")]).await;
+
+        let err = client(3)
+            .get_json::<Value>(&[lagging, html.clone()], "/checkpoints/1")
+            .await
+            .unwrap_err();
+        let err = err.downcast_ref::<HttpError>().unwrap();
+        assert_eq!(err.url, format!("{}/checkpoints/1", html));
+        assert!(err.reason.starts_with("invalid response"), "{}", err);
+
+        // Counted for the source of the client
+        assert!(crate::metrics::metrics()
+            .render()
+            .contains("zk_checkpoint_fetch_errors_total{source=\"http-test\"}"));
+    }
+
+    #[tokio::test]
+    async fn test_error_body() {
+        let url = endpoint(vec![(404, r#"{"error":"checkpoint not found"}"#)]).await;
+
+        let err = client(3)
+            .get_json::<Value>(std::slice::from_ref(&url), "/checkpoints/1")
+            .await
+            .unwrap_err();
+        let err = err.downcast_ref::<HttpError>().unwrap();
+        assert_eq!(err.url, format!("{}/checkpoints/1", url));
+        assert_eq!(err.status, Some(StatusCode::NOT_FOUND));
+        assert!(err.body.as_ref().unwrap().contains("checkpoint not found"));
+        assert!(!err.is_retryable());
+    }
+}
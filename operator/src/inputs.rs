@@ -0,0 +1,703 @@
+use crate::heimdall::HeimdallSource;
+use crate::sources::{BorSource, L1Source, CHECKPOINT_ID_INTERVAL};
+
+use alloy_primitives::{hex, Address, B256};
+use checkpoint_proof::exit::{block_leaf, checkpoint_branch, checkpoint_root};
+use checkpoint_proof::types::heimdall_types::CheckpointMsg;
+use eyre::Result;
+use sp1_sdk::SP1Stdin;
+use tracing::{info, info_span, instrument, warn, Instrument};
+use zk_checkpoint_lib::stake_manager::{
+    current_epoch_slot, staker_count_slot, total_stake_slot, validator_slots,
+};
+use zk_checkpoint_lib::validator_set::ValidatorSet;
+use zk_checkpoint_lib::CheckpointProofInput;
+
+/// Fetches the signatures of the validators which voted yes on the side tx (e.g. a checkpoint
+/// or span proposal) at the given height, along with their signers. The votes are part of the
+/// precommits included in the block at height + 2.
+pub async fn fetch_side_tx_sigs(
+    heimdall: &dyn HeimdallSource,
+    tx_hash: B256,
+    height: u64,
+) -> Result<(Vec<String>, Vec<Address>)> {
+    let block = heimdall.fetch_block_by_number(height + 2).await?;
+    let block_precommits = block.result.block.last_commit.precommits;
+
+    let mut sigs: Vec<String> = [].to_vec();
+    let mut signers: Vec<Address> = [].to_vec();
+
+    for precommit in block_precommits.iter() {
+        // Only add if the side tx result is non empty
+        if let Some(side_tx) = precommit.side_tx_results.as_ref() {
+            for tx in side_tx.iter() {
+                // Only add for requested tx with success result
+                if tx.tx_hash.0 == tx_hash.as_slice() && tx.result == 1 {
+                    let sig = tx.sig.as_ref().ok_or_else(|| {
+                        eyre::eyre!("missing sig of validator {}", precommit.validator_address)
+                    })?;
+                    sigs.push(sig.to_string());
+                    signers.push(precommit.validator_address);
+                }
+            }
+        }
+    }
+    Ok((sigs, signers))
+}
+
+/// Parameters identifying the checkpoint to be proven along with the network it belongs to.
+#[derive(Debug, Clone)]
+pub struct CheckpointParams {
+    pub checkpoint_id: u64,
+    pub checkpoint_tx_hash: String,
+    pub l1_block_number: u64,
+    pub bor_chain_id: u64,
+    pub root_chain: Address,
+    pub stake_manager: Address,
+    /// Only warn (instead of failing) if L1 already received the checkpoint, e.g. for proving
+    /// old checkpoints. It still has to match the header block on L1.
+    pub allow_received: bool,
+}
+
+/// Writes the inputs in the order they're read by the checkpoint program.
+pub fn checkpoint_stdin(input: &CheckpointProofInput) -> SP1Stdin {
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&input.tx_data);
+    stdin.write(&input.tx_hash);
+    stdin.write(&input.sigs);
+    stdin.write(&input.signers);
+    stdin.write(&input.validator_ids);
+    stdin.write(&input.state_sketch_bytes);
+    stdin.write(&input.root_chain_address);
+    stdin.write(&input.stake_manager_address);
+    stdin.write(&input.stake_manager_proof);
+    stdin.write(&input.l1_block_hash);
+    stdin.write(&input.bor_start_header);
+    stdin.write(&input.bor_start_branch);
+    stdin.write(&input.bor_end_header);
+    stdin.write(&input.bor_end_branch);
+    stdin.write(&input.bor_chain_id);
+    stdin
+}
+
+/// Cross-checks the checkpoint against the header blocks received by the root chain at the L1
+/// block. The verifier only accepts the checkpoint following the latest header block, so besides
+/// checkpoints which diverge from L1, the ones L1 already has (unless `allow_received`) or which
+/// don't follow it are refused as well.
+async fn check_root_chain(
+    l1: &dyn L1Source,
+    params: &CheckpointParams,
+    msg: &CheckpointMsg,
+) -> Result<()> {
+    let latest_id = l1
+        .current_header_block(params.root_chain, params.l1_block_number)
+        .await?
+        / CHECKPOINT_ID_INTERVAL;
+
+    if params.checkpoint_id <= latest_id {
+        let id = params.checkpoint_id * CHECKPOINT_ID_INTERVAL;
+        let header_block = l1
+            .header_block(params.root_chain, id, params.l1_block_number)
+            .await?;
+        let mut mismatches = Vec::new();
+        if header_block.root.as_slice() != msg.root_hash {
+            mismatches.push(format!(
+                "root ({} != {})",
+                hex::encode_prefixed(&msg.root_hash),
+                header_block.root
+            ));
+        }
+        if header_block.start != msg.start_block {
+            mismatches.push(format!(
+                "start ({} != {})",
+                msg.start_block, header_block.start
+            ));
+        }
+        if header_block.end != msg.end_block {
+            mismatches.push(format!("end ({} != {})", msg.end_block, header_block.end));
+        }
+        if !mismatches.is_empty() {
+            eyre::bail!(
+                "checkpoint {} diverges from L1 header block {}: {}",
+                params.checkpoint_id,
+                id,
+                mismatches.join(", ")
+            );
+        }
+        if !params.allow_received {
+            eyre::bail!(
+                "checkpoint {} was already received by L1 at block {}, the next checkpoint is {}",
+                params.checkpoint_id,
+                params.l1_block_number,
+                latest_id + 1
+            );
+        }
+        warn!(
+            header_block = id,
+            latest_id, "Checkpoint was already received by L1"
+        );
+        return Ok(());
+    }
+    if params.checkpoint_id > latest_id + 1 {
+        eyre::bail!(
+            "checkpoint {} doesn't follow the latest checkpoint on L1 at block {} ({})",
+            params.checkpoint_id,
+            params.l1_block_number,
+            latest_id
+        );
+    }
+
+    // The checkpoint needs to continue from the latest header block
+    if latest_id > 0 {
+        let id = latest_id * CHECKPOINT_ID_INTERVAL;
+        let header_block = l1
+            .header_block(params.root_chain, id, params.l1_block_number)
+            .await?;
+        if header_block.end + 1 != msg.start_block {
+            eyre::bail!(
+                "checkpoint {} diverges from L1 header block {}: start ({}) doesn't follow end ({})",
+                params.checkpoint_id,
+                id,
+                msg.start_block,
+                header_block.end
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Generates the inputs of the checkpoint program from the given sources. Returns the active
+/// validator set along with the inputs, to be used as the previous set in the next proof.
+#[instrument(
+    name = "fetch",
+    skip_all,
+    fields(checkpoint_id = params.checkpoint_id, l1_block_number = params.l1_block_number)
+)]
+pub async fn generate_inputs(
+    heimdall: &dyn HeimdallSource,
+    l1: &dyn L1Source,
+    bor: &dyn BorSource,
+    params: &CheckpointParams,
+) -> Result<(CheckpointProofInput, ValidatorSet)> {
+    // Fetch checkpoint object and it's tx data
+    let checkpoint = heimdall
+        .fetch_checkpoint_by_id(params.checkpoint_id)
+        .await?;
+    if checkpoint.result.bor_chain_id != params.bor_chain_id {
+        eyre::bail!(
+            "checkpoint {} is for bor chain {}, expected: {}",
+            params.checkpoint_id,
+            checkpoint.result.bor_chain_id,
+            params.bor_chain_id
+        );
+    }
+    let tx = heimdall
+        .fetch_tx_by_hash(params.checkpoint_tx_hash.clone())
+        .await?;
+    let msg = tx.result.checkpoint_msg()?;
+    checkpoint
+        .result
+        .check_msg(&msg)
+        .map_err(|err| eyre::eyre!("checkpoint {}: {}", params.checkpoint_id, err))?;
+    check_root_chain(l1, params, &msg).await?;
+    let tx_data = tx.result.tx.to_string();
+    let tx_hash = tx.result.hash;
+
+    let (sigs, signers) = fetch_side_tx_sigs(heimdall, tx_hash, tx.result.height).await?;
+    info!(%tx_hash, signatures = sigs.len(), "Fetched the checkpoint tx signatures");
+
+    // Assemble the evm sketch of the root chain call to be sent to prover
+    let sketch = l1
+        .root_chain_sketch(params.root_chain, params.l1_block_number)
+        .instrument(info_span!("sketch"))
+        .await?;
+    let l1_block_hash = sketch.block_hash;
+    let state_sketch_bytes = sketch.sketch.to_vec();
+
+    // Fetch the active validator set, all of which are proven by the program
+    let validator_set = l1
+        .validator_set(params.stake_manager, params.l1_block_number)
+        .await?;
+    let validator_ids: Vec<u64> = validator_set.validators.iter().map(|v| v.id).collect();
+    info!(
+        validators = validator_ids.len(),
+        "Fetched the active validator set"
+    );
+
+    // Fetch the storage proofs of the stake manager slots required to derive the signer
+    // and stake of each validator along with the totals
+    let mut keys = vec![
+        total_stake_slot(),
+        staker_count_slot(),
+        current_epoch_slot(),
+    ];
+    for id in validator_ids.iter() {
+        keys.extend(validator_slots(*id));
+    }
+    let stake_manager_proof = l1
+        .account_proof(params.stake_manager, keys, params.l1_block_number)
+        .await?;
+
+    // The checkpoint needs to continue from the previous one, except for the first checkpoint
+    // which starts at the genesis block
+    if params.checkpoint_id > 1 {
+        let prev_checkpoint = heimdall
+            .fetch_checkpoint_by_id(params.checkpoint_id - 1)
+            .await?;
+        if checkpoint.result.start_block.checked_sub(1) != Some(prev_checkpoint.result.end_block) {
+            eyre::bail!(
+                "checkpoint {} doesn't continue from the previous checkpoint, start: {}, previous end: {}",
+                params.checkpoint_id,
+                checkpoint.result.start_block,
+                prev_checkpoint.result.end_block
+            );
+        }
+    }
+
+    // Fetch the bor headers of the checkpoint to build the branches of the first and last
+    // block in the checkpoint root hash, which the program checks the committed hashes against
+    let (start_block, end_block) = (checkpoint.result.start_block, checkpoint.result.end_block);
+    if end_block < start_block {
+        eyre::bail!(
+            "checkpoint {} ends before it starts, start: {}, end: {}",
+            params.checkpoint_id,
+            start_block,
+            end_block
+        );
+    }
+    let headers = bor
+        .headers(start_block, end_block)
+        .instrument(info_span!("bor_headers"))
+        .await?;
+    let leaves: Vec<B256> = headers
+        .iter()
+        .map(|header| {
+            block_leaf(
+                header.number,
+                header.timestamp,
+                header.transactions_root,
+                header.receipts_root,
+            )
+        })
+        .collect();
+    let root_hash = checkpoint_root(&leaves);
+    if root_hash != checkpoint.result.root_hash {
+        eyre::bail!(
+            "bor blocks {} to {} don't match the root hash of checkpoint {} ({} != {})",
+            start_block,
+            end_block,
+            params.checkpoint_id,
+            root_hash,
+            checkpoint.result.root_hash
+        );
+    }
+    let last = headers.len() - 1;
+
+    let input = CheckpointProofInput {
+        tx_data,
+        tx_hash,
+        sigs,
+        signers,
+        validator_ids,
+        state_sketch_bytes,
+        root_chain_address: params.root_chain,
+        stake_manager_address: params.stake_manager,
+        stake_manager_proof,
+        l1_block_hash,
+        bor_start_header: alloy_rlp::encode(&headers[0]).into(),
+        bor_start_branch: checkpoint_branch(&leaves, 0),
+        bor_end_header: alloy_rlp::encode(&headers[last]).into(),
+        bor_end_branch: checkpoint_branch(&leaves, last),
+        bor_chain_id: params.bor_chain_id,
+        checkpoint_id: params.checkpoint_id,
+    };
+
+    Ok((input, validator_set))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::{HeaderBlock, L1Sketch, MemorySource};
+    use crate::types::tests::{CHECKPOINT_TX, CHECKPOINT_TX_HASH};
+    use crate::types::{
+        Base64Bytes, Block, BlockResponse, BlockResponseResult, Checkpoint, CheckpointResponse,
+        LastCommit, Precommit, SideTxResult, TxResponse, TxResponseResult,
+    };
+    use alloy_primitives::{address, b256, Bytes, B256, U256};
+    use base64::{prelude::BASE64_STANDARD, Engine};
+    use checkpoint_proof::helper::verify_checkpoint_header;
+    use reth_primitives::Header;
+    use std::str::FromStr;
+    use zk_checkpoint_lib::stake_manager::{AccountProof, StorageProof};
+    use zk_checkpoint_lib::validator_set::Validator;
+
+    const L1_BLOCK: u64 = 1000;
+    // Bor blocks of the checkpoint proposed by `CHECKPOINT_TX`
+    const START_BLOCK: u64 = 13383283;
+    const END_BLOCK: u64 = 13383794;
+
+    /// Synthetic bor header, as only the fields of the checkpoint leaf matter.
+    fn bor_header(number: u64) -> Header {
+        Header {
+            number,
+            timestamp: 1_700_000_000 + 2 * number,
+            parent_hash: B256::from(U256::from(number - 1)),
+            transactions_root: B256::with_last_byte(number as u8),
+            ..Default::default()
+        }
+    }
+
+    fn bor_root(start_block: u64, end_block: u64) -> B256 {
+        let leaves: Vec<B256> = (start_block..=end_block)
+            .map(bor_header)
+            .map(|header| {
+                block_leaf(
+                    header.number,
+                    header.timestamp,
+                    header.transactions_root,
+                    header.receipts_root,
+                )
+            })
+            .collect();
+        checkpoint_root(&leaves)
+    }
+
+    /// `CHECKPOINT_TX` with it's root hash replaced by the root of the synthetic bor headers.
+    fn checkpoint_tx() -> Vec<u8> {
+        let mut tx = BASE64_STANDARD.decode(CHECKPOINT_TX).unwrap();
+        let root_hash = b256!("6d77ca37ba082713c22b17f24d27697db8f48f76248a6c09b714fdfe4f9e9fb1");
+        let offset = tx
+            .windows(32)
+            .position(|window| window == root_hash.as_slice())
+            .unwrap();
+        tx[offset..offset + 32].copy_from_slice(bor_root(START_BLOCK, END_BLOCK).as_slice());
+        tx
+    }
+
+    fn checkpoint(start_block: u64, end_block: u64) -> CheckpointResponse {
+        CheckpointResponse {
+            result: Checkpoint {
+                proposer: address!("6dc2dd54f24979ec26212794c71afefed722280c"),
+                start_block,
+                end_block,
+                root_hash: bor_root(start_block, end_block),
+                bor_chain_id: 80002,
+                timestamp: 0,
+            },
+        }
+    }
+
+    fn header_block(checkpoint: &CheckpointResponse) -> HeaderBlock {
+        HeaderBlock {
+            root: checkpoint.result.root_hash,
+            start: checkpoint.result.start_block,
+            end: checkpoint.result.end_block,
+            proposer: checkpoint.result.proposer,
+        }
+    }
+
+    fn precommit(validator: Address, tx_hash: B256, sig: &[u8]) -> Precommit {
+        Precommit {
+            validator_address: validator,
+            side_tx_results: Some(vec![SideTxResult {
+                tx_hash: Base64Bytes(tx_hash.to_vec()),
+                result: 1,
+                sig: Some(Base64Bytes(sig.to_vec())),
+            }]),
+        }
+    }
+
+    fn tx_hash() -> B256 {
+        B256::from_str(CHECKPOINT_TX_HASH).unwrap()
+    }
+
+    /// Sets the header blocks of the root chain at `L1_BLOCK` to the checkpoints (on heimdall)
+    /// up to the given one.
+    fn receive_on_l1(fake: &mut MemorySource, latest_id: u64) {
+        fake.current_header_blocks
+            .insert(L1_BLOCK, latest_id * CHECKPOINT_ID_INTERVAL);
+        fake.header_blocks = (1..=latest_id)
+            .map(|id| {
+                (
+                    id * CHECKPOINT_ID_INTERVAL,
+                    header_block(&fake.checkpoints[&id]),
+                )
+            })
+            .collect();
+    }
+
+    /// Fake network where the checkpoint 2 is signed by one of the two active validators and
+    /// L1 is at checkpoint 1.
+    fn fake() -> MemorySource {
+        let mut fake = MemorySource::default();
+        fake.checkpoints
+            .insert(1, checkpoint(START_BLOCK - 100, START_BLOCK - 1));
+        fake.checkpoints
+            .insert(2, checkpoint(START_BLOCK, END_BLOCK));
+        receive_on_l1(&mut fake, 1);
+        fake.txs.insert(
+            CHECKPOINT_TX_HASH.to_string(),
+            TxResponse {
+                result: TxResponseResult {
+                    hash: tx_hash(),
+                    height: 10,
+                    tx: Base64Bytes(checkpoint_tx()),
+                },
+            },
+        );
+        fake.heimdall_blocks.insert(
+            12,
+            BlockResponse {
+                result: BlockResponseResult {
+                    block: Block {
+                        last_commit: LastCommit {
+                            precommits: vec![
+                                precommit(Address::with_last_byte(1), tx_hash(), b"sig1"),
+                                precommit(Address::with_last_byte(2), B256::ZERO, b"sig2"),
+                            ],
+                        },
+                    },
+                },
+            },
+        );
+        fake.l1_sketches.insert(
+            L1_BLOCK,
+            L1Sketch {
+                block_hash: B256::with_last_byte(1),
+                sketch: Bytes::from(vec![1, 2, 3]),
+            },
+        );
+
+        let mut validator_set = ValidatorSet::default();
+        for id in 1..=2 {
+            validator_set.upsert(Validator {
+                id,
+                signer: Address::with_last_byte(id as u8),
+                stake: U256::from(100),
+            });
+        }
+        fake.validator_sets.insert(L1_BLOCK, validator_set);
+
+        let mut keys = vec![
+            total_stake_slot(),
+            staker_count_slot(),
+            current_epoch_slot(),
+        ];
+        keys.extend(validator_slots(2));
+        keys.extend(validator_slots(1));
+        fake.account_proofs.insert(
+            L1_BLOCK,
+            AccountProof {
+                nonce: 0,
+                balance: U256::ZERO,
+                code_hash: B256::ZERO,
+                storage_hash: B256::ZERO,
+                account_proof: vec![],
+                storage_proofs: keys
+                    .into_iter()
+                    .map(|key| StorageProof {
+                        key,
+                        value: U256::ZERO,
+                        proof: vec![],
+                    })
+                    .collect(),
+            },
+        );
+
+        for number in START_BLOCK - 100..=END_BLOCK {
+            fake.bor_headers.insert(number, bor_header(number));
+        }
+        fake
+    }
+
+    fn params() -> CheckpointParams {
+        CheckpointParams {
+            checkpoint_id: 2,
+            checkpoint_tx_hash: CHECKPOINT_TX_HASH.to_string(),
+            l1_block_number: L1_BLOCK,
+            bor_chain_id: 80002,
+            root_chain: Address::with_last_byte(10),
+            stake_manager: Address::with_last_byte(11),
+            allow_received: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_inputs() {
+        let fake = fake();
+        let (input, validator_set) = generate_inputs(&fake, &fake, &fake, &params())
+            .await
+            .unwrap();
+
+        // Only the signature of the checkpoint tx is included
+        assert_eq!(input.tx_hash, tx_hash());
+        assert_eq!(input.tx_data, BASE64_STANDARD.encode(checkpoint_tx()));
+        assert_eq!(input.sigs, vec![BASE64_STANDARD.encode(b"sig1")]);
+        assert_eq!(input.signers, vec![Address::with_last_byte(1)]);
+
+        // All validators are proven, with the slots in the requested order
+        assert_eq!(input.validator_ids, vec![1, 2]);
+        let keys: Vec<B256> = input
+            .stake_manager_proof
+            .storage_proofs
+            .iter()
+            .map(|storage_proof| storage_proof.key)
+            .collect();
+        assert_eq!(keys[3..], [validator_slots(1), validator_slots(2)].concat());
+
+        assert_eq!(input.l1_block_hash, B256::with_last_byte(1));
+        assert_eq!(input.state_sketch_bytes, vec![1, 2, 3]);
+        // The first and last bor headers are included in the checkpoint root hash
+        let root_hash = bor_root(START_BLOCK, END_BLOCK);
+        let (start_header, _) = verify_checkpoint_header(
+            &input.bor_start_header,
+            &input.bor_start_branch,
+            START_BLOCK,
+            START_BLOCK,
+            root_hash,
+        );
+        assert_eq!(start_header, bor_header(START_BLOCK));
+        let (_, end_hash) = verify_checkpoint_header(
+            &input.bor_end_header,
+            &input.bor_end_branch,
+            END_BLOCK,
+            START_BLOCK,
+            root_hash,
+        );
+        assert_eq!(end_hash, bor_header(END_BLOCK).hash_slow());
+        assert_eq!(input.bor_chain_id, 80002);
+        assert_eq!(validator_set.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_generate_inputs_wrong_chain() {
+        let fake = fake();
+        let mut params = params();
+        params.bor_chain_id = 137;
+        let err = generate_inputs(&fake, &fake, &fake, &params)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("is for bor chain 80002"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_inputs_checkpoint_mismatch() {
+        let mut fake = fake();
+        let checkpoint = fake.checkpoints.get_mut(&2).unwrap();
+        checkpoint.result.root_hash = B256::ZERO;
+        let err = generate_inputs(&fake, &fake, &fake, &params())
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("checkpoint 2: checkpoint doesn't match the checkpoint tx: root hash"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_inputs_bor_root_mismatch() {
+        let mut fake = fake();
+        let header = fake.bor_headers.get_mut(&(END_BLOCK - 1)).unwrap();
+        header.receipts_root = B256::with_last_byte(1);
+        let err = generate_inputs(&fake, &fake, &fake, &params())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with(&format!(
+            "bor blocks {} to {} don't match the root hash of checkpoint 2",
+            START_BLOCK, END_BLOCK
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_generate_inputs_discontinuous() {
+        let mut fake = fake();
+        let previous = fake.checkpoints.get_mut(&1).unwrap();
+        previous.result.end_block -= 1;
+        let err = generate_inputs(&fake, &fake, &fake, &params())
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("checkpoint 2 doesn't continue from the previous checkpoint"));
+    }
+
+    async fn root_chain_err(fake: &MemorySource, params: &CheckpointParams) -> String {
+        generate_inputs(fake, fake, fake, params)
+            .await
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_generate_inputs_root_chain_one_behind() {
+        // Heimdall is at checkpoint 3 while L1 only received checkpoint 1, so checkpoint 2 is
+        // the next one for L1
+        let mut fake = fake();
+        fake.checkpoints
+            .insert(3, checkpoint(END_BLOCK + 1, END_BLOCK + 100));
+        receive_on_l1(&mut fake, 1);
+        let (input, _) = generate_inputs(&fake, &fake, &fake, &params())
+            .await
+            .unwrap();
+        assert_eq!(input.checkpoint_id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_generate_inputs_root_chain_received() {
+        let mut fake = fake();
+        receive_on_l1(&mut fake, 2);
+        let err = root_chain_err(&fake, &params()).await;
+        assert!(err.starts_with(
+            "checkpoint 2 was already received by L1 at block 1000, the next checkpoint is 3"
+        ));
+
+        // Only warns if allowed
+        let mut params = params();
+        params.allow_received = true;
+        generate_inputs(&fake, &fake, &fake, &params).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_generate_inputs_root_chain_received_diverges() {
+        let mut fake = fake();
+        receive_on_l1(&mut fake, 2);
+        let header_block = fake
+            .header_blocks
+            .get_mut(&(2 * CHECKPOINT_ID_INTERVAL))
+            .unwrap();
+        header_block.root = B256::ZERO;
+
+        // Also refused when received checkpoints are allowed
+        let mut params = params();
+        params.allow_received = true;
+        let err = root_chain_err(&fake, &params).await;
+        assert!(
+            err.starts_with("checkpoint 2 diverges from L1 header block 20000: root"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_inputs_root_chain_behind() {
+        // L1 doesn't have the previous checkpoint yet
+        let mut fake = fake();
+        receive_on_l1(&mut fake, 0);
+        let err = root_chain_err(&fake, &params()).await;
+        assert!(err.starts_with(
+            "checkpoint 2 doesn't follow the latest checkpoint on L1 at block 1000 (0)"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_generate_inputs_root_chain_previous_diverges() {
+        // L1 has a different previous checkpoint
+        let mut fake = fake();
+        let previous = fake.header_blocks.get_mut(&CHECKPOINT_ID_INTERVAL).unwrap();
+        previous.end -= 1;
+        let err = root_chain_err(&fake, &params()).await;
+        assert!(err.starts_with(
+            "checkpoint 2 diverges from L1 header block 10000: start (13383283) doesn't follow end (13383281)"
+        ));
+    }
+}
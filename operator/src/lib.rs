@@ -1,3 +1,21 @@
+pub mod beacon;
+pub mod cli;
+pub mod config;
 pub mod contract;
+pub mod exit;
+pub mod heimdall;
+pub mod http;
+pub mod inputs;
+pub mod logging;
+pub mod metrics;
+pub mod signer;
+pub mod sources;
+pub mod submit;
 pub mod types;
 pub mod utils;
+pub mod validators;
+
+/// ELFs of the programs (built by `build.rs`).
+pub const CHECKPOINT_ELF: &[u8] = include_bytes!("../../elf/checkpoint-proof");
+pub const AGGREGATION_ELF: &[u8] = include_bytes!("../../elf/aggregation-proof");
+pub const HEADER_CHAIN_ELF: &[u8] = include_bytes!("../../elf/header-chain-proof");
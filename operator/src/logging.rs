@@ -0,0 +1,55 @@
+use crate::config::env_var;
+
+use clap::ValueEnum;
+use eyre::Result;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+/// Filter used if neither `--log-level` nor `RUST_LOG` is set. Only the operator (and it's
+/// binary) log at info level as the prover is very verbose.
+pub const DEFAULT_LOG_FILTER: &str = "warn,zk_checkpoint_operator=info,zk_checkpoint=info";
+
+/// Format of the logs, which are written to stderr.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human readable lines.
+    #[default]
+    Text,
+    /// A json object per line, along with the fields of the spans it was logged in.
+    Json,
+}
+
+/// Sets up the global logger. The level is a filter such as `debug` or
+/// `info,zk_checkpoint_operator=debug` and defaults to `RUST_LOG`, the format defaults to
+/// `LOG_FORMAT`. Spans (e.g. fetching the inputs or proving) are logged along with their
+/// duration when they close.
+pub fn init(level: Option<&str>, format: Option<LogFormat>) -> Result<()> {
+    let level = match level {
+        Some(level) => level.to_string(),
+        None => env_var("RUST_LOG").unwrap_or_else(|| DEFAULT_LOG_FILTER.to_string()),
+    };
+    let filter = EnvFilter::try_new(&level)
+        .map_err(|err| eyre::eyre!("invalid log level {:?}: {}", level, err))?;
+    let format = match format {
+        Some(format) => format,
+        None => match env_var("LOG_FORMAT") {
+            Some(value) => LogFormat::from_str(&value, true)
+                .map_err(|err| eyre::eyre!("invalid LOG_FORMAT: {}", err))?,
+            None => LogFormat::default(),
+        },
+    };
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr);
+    match format {
+        LogFormat::Text => builder.try_init(),
+        LogFormat::Json => builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .try_init(),
+    }
+    .map_err(|err| eyre::eyre!("unable to set up logging: {}", err))
+}
@@ -0,0 +1,296 @@
+use eyre::Result;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use prometheus::{CounterVec, Encoder, GaugeVec, Opts, Registry, TextEncoder, TEXT_FORMAT};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::{debug, Instrument};
+
+// Connections which don't send the request headers in time are closed
+const HEADER_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A gauge with an optional label. Values which were never set aren't exported (rather than
+/// as 0, which would be a valid checkpoint id or lag).
+#[derive(Debug)]
+pub struct Gauge(GaugeVec);
+
+impl Gauge {
+    fn new(registry: &Registry, name: &str, help: &str, label: Option<&str>) -> Self {
+        let gauge = GaugeVec::new(Opts::new(name, help), label.as_slice()).expect("invalid gauge");
+        registry
+            .register(Box::new(gauge.clone()))
+            .expect("gauge registered twice");
+        Self(gauge)
+    }
+
+    pub fn set(&self, value: f64) {
+        self.0.with_label_values(&[]).set(value);
+    }
+
+    /// Sets the value for the given label value (e.g. the destination of a submission).
+    pub fn set_with(&self, label: &str, value: f64) {
+        self.0.with_label_values(&[label]).set(value);
+    }
+}
+
+/// A counter per label value.
+#[derive(Debug)]
+pub struct Counter(CounterVec);
+
+impl Counter {
+    fn new(registry: &Registry, name: &str, help: &str, label: &str) -> Self {
+        let counter = CounterVec::new(Opts::new(name, help), &[label]).expect("invalid counter");
+        registry
+            .register(Box::new(counter.clone()))
+            .expect("counter registered twice");
+        Self(counter)
+    }
+
+    pub fn inc_with(&self, label: &str) {
+        self.0.with_label_values(&[label]).inc();
+    }
+}
+
+/// Metrics of the operator, exported in the prometheus text format on `/metrics`.
+#[derive(Debug)]
+pub struct Metrics {
+    registry: Registry,
+    /// Cycles of the last execution of the checkpoint program.
+    pub cycles: Gauge,
+    /// Wall time of the last proof.
+    pub proving_seconds: Gauge,
+    pub proof_size_bytes: Gauge,
+    /// Gas used by the last submission, per destination.
+    pub submission_gas_used: Gauge,
+    pub last_proved_checkpoint_id: Gauge,
+    pub last_submitted_checkpoint_id: Gauge,
+    /// Number of checkpoints on heimdall which aren't verified yet.
+    pub heimdall_lag: Gauge,
+    /// Failed requests (including retried ones) per source, e.g. heimdall, beacon, l1 or bor.
+    pub fetch_errors: Counter,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        let registry = Registry::new();
+        Self {
+            cycles: Gauge::new(
+                &registry,
+                "zk_checkpoint_cycles",
+                "Cycles of the last execution of the checkpoint program.",
+                None,
+            ),
+            proving_seconds: Gauge::new(
+                &registry,
+                "zk_checkpoint_proving_seconds",
+                "Wall time of the last proof in seconds.",
+                None,
+            ),
+            proof_size_bytes: Gauge::new(
+                &registry,
+                "zk_checkpoint_proof_size_bytes",
+                "Size of the last proof in bytes.",
+                None,
+            ),
+            submission_gas_used: Gauge::new(
+                &registry,
+                "zk_checkpoint_submission_gas_used",
+                "Gas used by the last submission of a proof.",
+                Some("destination"),
+            ),
+            last_proved_checkpoint_id: Gauge::new(
+                &registry,
+                "zk_checkpoint_last_proved_checkpoint_id",
+                "Id of the last proved checkpoint.",
+                None,
+            ),
+            last_submitted_checkpoint_id: Gauge::new(
+                &registry,
+                "zk_checkpoint_last_submitted_checkpoint_id",
+                "Id of the last checkpoint verified on-chain.",
+                None,
+            ),
+            heimdall_lag: Gauge::new(
+                &registry,
+                "zk_checkpoint_heimdall_lag",
+                "Number of checkpoints on heimdall which aren't verified on-chain yet.",
+                None,
+            ),
+            fetch_errors: Counter::new(
+                &registry,
+                "zk_checkpoint_fetch_errors_total",
+                "Number of failed requests to heimdall, the beacon node, L1 and bor.",
+                "source",
+            ),
+            registry,
+        }
+    }
+}
+
+impl Metrics {
+    /// Renders the metrics in the prometheus text format.
+    pub fn render(&self) -> String {
+        let mut out = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut out)
+            .expect("metrics can be encoded");
+        String::from_utf8(out).expect("metrics are utf-8")
+    }
+}
+
+/// Metrics of the process, recorded by the commands and the sources.
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::default)
+}
+
+/// Returns a callback counting a failed request to the source, to be used with `inspect_err`.
+pub fn fetch_error<E>(source: &'static str) -> impl Fn(&E) {
+    move |_| metrics().fetch_errors.inc_with(source)
+}
+
+/// Serves the metrics on `/metrics` at the given address (e.g. `0.0.0.0:9090`) in the
+/// background, returning the address it's bound to.
+pub async fn serve(addr: &str) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|err| eyre::eyre!("unable to serve metrics on {}: {}", addr, err))?;
+    let local_addr = listener.local_addr()?;
+    tokio::spawn(serve_metrics(listener, metrics(), HEADER_READ_TIMEOUT).in_current_span());
+    Ok(local_addr)
+}
+
+async fn serve_metrics(listener: TcpListener, metrics: &'static Metrics, read_timeout: Duration) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                debug!(%err, "Unable to accept metrics connection");
+                continue;
+            }
+        };
+        let service = service_fn(move |request: Request<Incoming>| {
+            let response = respond(&request, metrics);
+            async move { Ok::<_, Infallible>(response) }
+        });
+        tokio::spawn(async move {
+            let result = http1::Builder::new()
+                .timer(TokioTimer::new())
+                .header_read_timeout(read_timeout)
+                .serve_connection(TokioIo::new(stream), service)
+                .await;
+            if let Err(err) = result {
+                debug!(%peer, %err, "Metrics connection failed");
+            }
+        });
+    }
+}
+
+/// Responds with the metrics to a `GET` of `/metrics` and with a 404 otherwise.
+fn respond(request: &Request<Incoming>, metrics: &Metrics) -> Response<Full<Bytes>> {
+    let (status, body) = match (request.method(), request.uri().path()) {
+        (&Method::GET, "/metrics") => (StatusCode::OK, metrics.render()),
+        _ => (StatusCode::NOT_FOUND, "not found\n".to_string()),
+    };
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(TEXT_FORMAT));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::default();
+        metrics.cycles.set(12345.0);
+        metrics.submission_gas_used.set_with("amoy", 250000.0);
+        metrics
+            .submission_gas_used
+            .set_with("sepolia \"2\"", 260000.0);
+        metrics.fetch_errors.inc_with("heimdall");
+        metrics.fetch_errors.inc_with("heimdall");
+
+        let rendered = metrics.render();
+        assert!(rendered.contains(
+            "# HELP zk_checkpoint_cycles Cycles of the last execution of the checkpoint program.\n# TYPE zk_checkpoint_cycles gauge\nzk_checkpoint_cycles 12345\n"
+        ));
+        assert!(
+            rendered.contains("zk_checkpoint_submission_gas_used{destination=\"amoy\"} 250000\n")
+        );
+        assert!(rendered.contains(
+            "zk_checkpoint_submission_gas_used{destination=\"sepolia \\\"2\\\"\"} 260000\n"
+        ));
+        assert!(rendered.contains(
+            "# TYPE zk_checkpoint_fetch_errors_total counter\nzk_checkpoint_fetch_errors_total{source=\"heimdall\"} 2\n"
+        ));
+        // Unset metrics aren't exported
+        assert!(!rendered.contains("zk_checkpoint_heimdall_lag"));
+    }
+
+    async fn start(metrics: &'static Metrics, read_timeout: Duration) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_metrics(listener, metrics, read_timeout));
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_serve() {
+        let metrics: &'static Metrics = Box::leak(Box::default());
+        metrics.heimdall_lag.set(3.0);
+        let addr = start(metrics, HEADER_READ_TIMEOUT).await;
+
+        let get = |path: &'static str| async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            let request = format!(
+                "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+                path
+            );
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let response = get("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(&format!("content-type: {}\r\n", TEXT_FORMAT)));
+        assert!(response.ends_with(&metrics.render()));
+        assert!(response.contains("\nzk_checkpoint_heimdall_lag 3\n"));
+
+        assert!(get("/").await.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_serve_closes_idle_connections() {
+        let metrics: &'static Metrics = Box::leak(Box::default());
+        let addr = start(metrics, Duration::from_millis(100)).await;
+
+        // The connection is closed as the headers are never completed
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\n")
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        let read = stream.read_to_end(&mut response);
+        let _ = tokio::time::timeout(Duration::from_secs(5), read)
+            .await
+            .expect("connection not closed");
+    }
+}
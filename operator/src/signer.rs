@@ -0,0 +1,405 @@
+use crate::config::env_var;
+
+use alloy_consensus::TxEnvelope;
+use alloy_eips::eip2718::{Decodable2718, Encodable2718};
+use alloy_network::{EthereumWallet, TransactionBuilder};
+use alloy_primitives::{Address, Bytes};
+use alloy_rpc_types::TransactionRequest;
+use alloy_signer_local::PrivateKeySigner;
+use eyre::Result;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use url::Url;
+
+/// Configuration of the signer used for sending txs, selected via the `SIGNER` env var (or the
+/// `signer` field of a destination).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum SignerConfig {
+    /// Raw private key read from the given env var.
+    PrivateKey {
+        #[serde(default = "default_private_key_env")]
+        env: String,
+    },
+    /// Encrypted (json) keystore file. The password is read from the file if given or
+    /// prompted for otherwise.
+    Keystore {
+        path: String,
+        password_file: Option<String>,
+    },
+    /// Remote signer exposing `eth_signTransaction` over json rpc (e.g. Web3Signer).
+    Remote { url: String, address: Address },
+    /// Doesn't sign anything, only allows reading from the contracts.
+    ReadOnly { address: Option<Address> },
+}
+
+pub fn default_private_key_env() -> String {
+    "PRIVATE_KEY".to_string()
+}
+
+impl SignerConfig {
+    /// Reads the config from env. `SIGNER` is one of `private-key` (default), `keystore`,
+    /// `remote` or `read-only` and the rest of the config is read from `PRIVATE_KEY`,
+    /// `KEYSTORE_PATH`, `KEYSTORE_PASSWORD_FILE`, `REMOTE_SIGNER_URL` and `SIGNER_ADDRESS`.
+    pub fn from_env() -> Result<Self> {
+        let kind = env_var("SIGNER").unwrap_or_else(|| "private-key".to_string());
+        let address = || -> Result<Option<Address>> {
+            env_var("SIGNER_ADDRESS")
+                .map(|address| address.trim().parse())
+                .transpose()
+                .map_err(|err| eyre::eyre!("invalid SIGNER_ADDRESS: {}", err))
+        };
+
+        match kind.trim() {
+            "private-key" => Ok(Self::PrivateKey {
+                env: default_private_key_env(),
+            }),
+            "keystore" => Ok(Self::Keystore {
+                path: env_var("KEYSTORE_PATH")
+                    .ok_or_else(|| eyre::eyre!("KEYSTORE_PATH not set"))?,
+                password_file: env_var("KEYSTORE_PASSWORD_FILE"),
+            }),
+            "remote" => Ok(Self::Remote {
+                url: env_var("REMOTE_SIGNER_URL")
+                    .ok_or_else(|| eyre::eyre!("REMOTE_SIGNER_URL not set"))?,
+                address: address()?.ok_or_else(|| eyre::eyre!("SIGNER_ADDRESS not set"))?,
+            }),
+            "read-only" => Ok(Self::ReadOnly {
+                address: address()?,
+            }),
+            _ => eyre::bail!("unknown signer: {}", kind),
+        }
+    }
+
+    /// Creates the signer, decrypting the keystore (if needed).
+    pub fn signer(&self) -> Result<Signer> {
+        match self {
+            Self::PrivateKey { env } => {
+                let private_key = env_var(env).ok_or_else(|| eyre::eyre!("{} not set", env))?;
+                let private_key = private_key.trim();
+                let private_key = private_key.strip_prefix("0x").unwrap_or(private_key);
+                Ok(Signer::Local(private_key.parse()?))
+            }
+            Self::Keystore {
+                path,
+                password_file,
+            } => {
+                let password = match password_file {
+                    Some(file) => fs::read_to_string(file)?.trim_end().to_string(),
+                    None => rpassword::prompt_password(format!("Password for {}: ", path))?,
+                };
+                Ok(Signer::Local(PrivateKeySigner::decrypt_keystore(
+                    path, password,
+                )?))
+            }
+            Self::Remote { url, address } => Ok(Signer::Remote(RemoteSigner::new(url, *address)?)),
+            Self::ReadOnly { address } => Ok(Signer::ReadOnly(address.unwrap_or_default())),
+        }
+    }
+}
+
+/// Signer of the txs sent to the contracts.
+pub enum Signer {
+    Local(PrivateKeySigner),
+    Remote(RemoteSigner),
+    ReadOnly(Address),
+}
+
+impl Signer {
+    pub fn address(&self) -> Address {
+        match self {
+            Self::Local(signer) => signer.address(),
+            Self::Remote(signer) => signer.address,
+            Self::ReadOnly(address) => *address,
+        }
+    }
+
+    /// Signs the (fully populated) transaction and returns it's raw (EIP-2718) encoding.
+    pub async fn sign_transaction(&self, tx: &TransactionRequest) -> Result<Bytes> {
+        match self {
+            Self::Local(signer) => {
+                let wallet = EthereumWallet::from(signer.clone());
+                let envelope = tx.clone().build(&wallet).await?;
+                Ok(envelope.encoded_2718().into())
+            }
+            Self::Remote(signer) => signer.sign_transaction(tx).await,
+            Self::ReadOnly(_) => eyre::bail!("read-only signer can't send transactions"),
+        }
+    }
+}
+
+/// Signer delegating to a remote service over json rpc using `eth_signTransaction` as
+/// supported by Web3Signer (and clef).
+pub struct RemoteSigner {
+    client: Client,
+    url: Url,
+    pub address: Address,
+}
+
+/// Web3Signer returns the raw tx directly while clef wraps it along with the decoded tx.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum SignTransactionResult {
+    Raw(Bytes),
+    Wrapped { raw: Bytes },
+}
+
+impl RemoteSigner {
+    pub fn new(url: &str, address: Address) -> Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            url: Url::parse(url)?,
+            address,
+        })
+    }
+
+    pub async fn sign_transaction(&self, tx: &TransactionRequest) -> Result<Bytes> {
+        let field = |value: Option<u128>, name: &str| -> Result<String> {
+            value
+                .map(|value| format!("{:#x}", value))
+                .ok_or_else(|| eyre::eyre!("{} not set for tx", name))
+        };
+        let params = json!({
+            "from": self.address,
+            "to": tx.to.and_then(|to| to.to().copied()),
+            "gas": field(tx.gas, "gas")?,
+            "maxFeePerGas": field(tx.max_fee_per_gas, "max fee per gas")?,
+            "maxPriorityFeePerGas": field(tx.max_priority_fee_per_gas, "priority fee")?,
+            "nonce": field(tx.nonce.map(Into::into), "nonce")?,
+            "chainId": field(tx.chain_id.map(Into::into), "chain id")?,
+            "value": format!("{:#x}", tx.value.unwrap_or_default()),
+            "data": tx.input.input().cloned().unwrap_or_default(),
+        });
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_signTransaction",
+            "params": [params],
+        });
+
+        let response: Value = self
+            .client
+            .post(self.url.clone())
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.get("error") {
+            eyre::bail!("remote signer error: {}", error);
+        }
+        let result = response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| eyre::eyre!("remote signer returned no result"))?;
+        let raw = match serde_json::from_value(result)? {
+            SignTransactionResult::Raw(raw) | SignTransactionResult::Wrapped { raw } => raw,
+        };
+        self.check_signed(tx, &raw)?;
+        Ok(raw)
+    }
+
+    /// Checks that the remote signer signed the requested tx using the configured address,
+    /// so that a misconfigured (or compromised) signer can't make us broadcast another tx.
+    fn check_signed(&self, tx: &TransactionRequest, raw: &Bytes) -> Result<()> {
+        let envelope = TxEnvelope::decode_2718(&mut raw.as_ref())
+            .map_err(|err| eyre::eyre!("remote signer returned an invalid tx: {}", err))?;
+        let signer = envelope.recover_signer()?;
+        eyre::ensure!(
+            signer == self.address,
+            "remote signer signed the tx with {} instead of {}",
+            signer,
+            self.address
+        );
+
+        let TxEnvelope::Eip1559(signed) = &envelope else {
+            eyre::bail!(
+                "remote signer returned a {:?} tx instead of an EIP-1559 one",
+                envelope.tx_type()
+            );
+        };
+        let signed = signed.tx();
+        let input = tx.input.input().cloned().unwrap_or_default();
+        let mismatch = [
+            ("to", tx.to != Some(signed.to)),
+            ("nonce", tx.nonce != Some(signed.nonce)),
+            ("chain id", tx.chain_id != Some(signed.chain_id)),
+            ("data", input != signed.input),
+            ("value", tx.value.unwrap_or_default() != signed.value),
+            ("gas limit", tx.gas != Some(signed.gas_limit)),
+            (
+                "max fee",
+                tx.max_fee_per_gas != Some(signed.max_fee_per_gas),
+            ),
+            (
+                "priority fee",
+                tx.max_priority_fee_per_gas != Some(signed.max_priority_fee_per_gas),
+            ),
+        ];
+        let mismatch: Vec<_> = mismatch
+            .into_iter()
+            .filter_map(|(field, differs)| differs.then_some(field))
+            .collect();
+        eyre::ensure!(
+            mismatch.is_empty(),
+            "remote signer signed a different tx, mismatched: {}",
+            mismatch.join(", ")
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{address, U256};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Local stand-in for a remote signer which answers a single `eth_signTransaction`
+    /// request by signing the (tampered) tx with the given key.
+    async fn serve_once(
+        listener: TcpListener,
+        signer: PrivateKeySigner,
+        tamper: fn(TransactionRequest) -> TransactionRequest,
+    ) {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = Vec::new();
+        let body = loop {
+            let mut chunk = [0u8; 4096];
+            let n = stream.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+            let request = String::from_utf8_lossy(&buf).to_string();
+            if let Some((head, body)) = request.split_once("\r\n\r\n") {
+                let length: usize = head
+                    .lines()
+                    .find_map(|line| {
+                        line.to_lowercase()
+                            .strip_prefix("content-length:")
+                            .map(|l| l.trim().parse().unwrap())
+                    })
+                    .unwrap();
+                if body.len() >= length {
+                    break body.to_string();
+                }
+            }
+        };
+
+        let request: Value = serde_json::from_str(&body).unwrap();
+        let params = &request["params"][0];
+        let quantity = |name: &str| {
+            u128::from_str_radix(params[name].as_str().unwrap().trim_start_matches("0x"), 16)
+                .unwrap()
+        };
+        let tx = TransactionRequest::default()
+            .with_from(params["from"].as_str().unwrap().parse().unwrap())
+            .with_to(params["to"].as_str().unwrap().parse().unwrap())
+            .with_gas_limit(quantity("gas"))
+            .with_max_fee_per_gas(quantity("maxFeePerGas"))
+            .with_max_priority_fee_per_gas(quantity("maxPriorityFeePerGas"))
+            .with_nonce(quantity("nonce") as u64)
+            .with_chain_id(quantity("chainId") as u64)
+            .with_input(params["data"].as_str().unwrap().parse::<Bytes>().unwrap());
+        let raw = Signer::Local(signer)
+            .sign_transaction(&tamper(tx))
+            .await
+            .unwrap();
+
+        let response = json!({ "jsonrpc": "2.0", "id": 1, "result": raw }).to_string();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+            response.len(),
+            response
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+    }
+
+    fn tx() -> TransactionRequest {
+        TransactionRequest::default()
+            .with_to(address!("6e67834E4B98dc8dac5a186eCcdD54C78e9863f2"))
+            .with_gas_limit(300_000)
+            .with_max_fee_per_gas(2_000_000_000)
+            .with_max_priority_fee_per_gas(1_000_000_000)
+            .with_nonce(7)
+            .with_chain_id(11155111)
+            .with_value(U256::ZERO)
+            .with_input(Bytes::from(vec![1, 2, 3]))
+    }
+
+    async fn sign_remote(
+        key: PrivateKeySigner,
+        address: Address,
+        tamper: fn(TransactionRequest) -> TransactionRequest,
+    ) -> Result<Bytes> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve_once(listener, key, tamper));
+        let remote = Signer::Remote(RemoteSigner::new(&url, address).unwrap());
+        let raw = remote.sign_transaction(&tx()).await;
+        server.await.unwrap();
+        raw
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer() {
+        let local = PrivateKeySigner::random();
+        let raw = sign_remote(local.clone(), local.address(), |tx| tx)
+            .await
+            .unwrap();
+
+        let envelope = TxEnvelope::decode_2718(&mut raw.as_ref()).unwrap();
+        assert_eq!(envelope.recover_signer().unwrap(), local.address());
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_wrong_address() {
+        let local = PrivateKeySigner::random();
+        let other = PrivateKeySigner::random().address();
+        let err = sign_remote(local, other, |tx| tx).await.unwrap_err();
+        assert!(err.to_string().contains("instead of"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_different_tx() {
+        let local = PrivateKeySigner::random();
+        let err = sign_remote(local.clone(), local.address(), |tx| {
+            tx.with_nonce(8).with_input(Bytes::from(vec![4]))
+        })
+        .await
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("mismatched: nonce, data"),
+            "{}",
+            err
+        );
+
+        // E.g. draining the signer through the value or the fees
+        let err = sign_remote(local.clone(), local.address(), |tx| {
+            tx.with_value(U256::from(1))
+                .with_gas_limit(1_000_000)
+                .with_max_fee_per_gas(3_000_000_000)
+                .with_max_priority_fee_per_gas(2_000_000_000)
+        })
+        .await
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("mismatched: value, gas limit, max fee, priority fee"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_only_signer() {
+        let signer = SignerConfig::ReadOnly { address: None }.signer().unwrap();
+        assert_eq!(signer.address(), Address::ZERO);
+        assert!(signer
+            .sign_transaction(&TransactionRequest::default())
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,618 @@
+use alloy_primitives::{Address, Bytes, B256, U256};
+use alloy_provider::{Provider, ReqwestProvider};
+use alloy_rpc_types::{BlockId, BlockNumberOrTag};
+use alloy_sol_types::SolCall;
+use async_trait::async_trait;
+use eyre::Result;
+use reth_primitives::Header;
+use serde::{Deserialize, Serialize};
+use sp1_cc_client_executor::ContractInput;
+use sp1_cc_host_executor::HostExecutor;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Mutex;
+use tokio::task::JoinSet;
+use zk_checkpoint_lib::stake_manager::{AccountProof, StorageProof};
+use zk_checkpoint_lib::validator_set::ValidatorSet;
+use zk_checkpoint_lib::{RootChain, CALLER};
+
+use crate::heimdall::HeimdallSource;
+use crate::metrics::fetch_error;
+use crate::types::{
+    BlockResponse, CheckpointResponse, MilestoneResponse, SpanResponse, TxResponse,
+};
+use crate::validators::fetch_validator_set;
+
+/// Source of the L1 data the inputs are generated from. Everything is read at the L1 block the
+/// proof is anchored to.
+#[async_trait]
+pub trait L1Source: Send + Sync {
+    /// Hash of the block along with the (serialized) evm sketch of the root chain call executed
+    /// by the program against it.
+    async fn root_chain_sketch(&self, root_chain: Address, block_number: u64) -> Result<L1Sketch>;
+
+    /// Id of the latest header block received by the root chain (i.e. the id of the latest
+    /// checkpoint times `CHECKPOINT_ID_INTERVAL`).
+    async fn current_header_block(&self, root_chain: Address, block_number: u64) -> Result<u64>;
+
+    /// Header block of the root chain with the given id.
+    async fn header_block(
+        &self,
+        root_chain: Address,
+        id: u64,
+        block_number: u64,
+    ) -> Result<HeaderBlock>;
+
+    /// Active validator set of the stake manager.
+    async fn validator_set(
+        &self,
+        stake_manager: Address,
+        block_number: u64,
+    ) -> Result<ValidatorSet>;
+
+    /// Proof of the account and of the given storage slots (in the same order).
+    async fn account_proof(
+        &self,
+        address: Address,
+        keys: Vec<B256>,
+        block_number: u64,
+    ) -> Result<AccountProof>;
+}
+
+/// Source of bor blocks.
+#[async_trait]
+pub trait BorSource: Send + Sync {
+    /// Fetches the headers of the blocks `start..=end`, in order.
+    async fn headers(&self, start: u64, end: u64) -> Result<Vec<Header>>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct L1Sketch {
+    pub block_hash: B256,
+    pub sketch: Bytes,
+}
+
+/// Interval between the ids of consecutive header blocks of the root chain.
+pub const CHECKPOINT_ID_INTERVAL: u64 = 10000;
+
+/// Number of bor headers fetched concurrently.
+const BOR_HEADER_BATCH_SIZE: usize = 32;
+
+/// Checkpoint as received by the root chain (`headerBlocks(id)`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderBlock {
+    pub root: B256,
+    pub start: u64,
+    pub end: u64,
+    pub proposer: Address,
+}
+
+fn to_u64(value: U256, what: &str) -> Result<u64> {
+    u64::try_from(value).map_err(|_| eyre::eyre!("{} out of range: {}", what, value))
+}
+
+/// L1 source reading from an rpc node (which needs to serve `eth_getProof` at the block).
+pub struct RpcL1Source {
+    provider: ReqwestProvider,
+}
+
+impl RpcL1Source {
+    pub fn new(provider: ReqwestProvider) -> Self {
+        Self { provider }
+    }
+
+    async fn call<C: SolCall>(
+        &self,
+        contract_address: Address,
+        calldata: C,
+        block_number: u64,
+    ) -> Result<C::Return> {
+        let mut host_executor = HostExecutor::new(
+            self.provider.clone(),
+            BlockNumberOrTag::Number(block_number),
+        )
+        .await
+        .inspect_err(fetch_error("l1"))?;
+        host_executor
+            .execute(ContractInput {
+                contract_address,
+                caller_address: CALLER,
+                calldata,
+            })
+            .await
+            .inspect_err(fetch_error("l1"))
+    }
+}
+
+#[async_trait]
+impl L1Source for RpcL1Source {
+    async fn root_chain_sketch(&self, root_chain: Address, block_number: u64) -> Result<L1Sketch> {
+        let mut host_executor = HostExecutor::new(
+            self.provider.clone(),
+            BlockNumberOrTag::Number(block_number),
+        )
+        .await
+        .inspect_err(fetch_error("l1"))?;
+        let block_hash = host_executor.header.hash_slow();
+
+        // Prepare and execute call to fetch last checkpoint from L1 contract
+        let call = RootChain::getLastChildBlockCall {};
+        let _response: RootChain::getLastChildBlockReturn = host_executor
+            .execute(ContractInput {
+                contract_address: root_chain,
+                caller_address: CALLER,
+                calldata: call,
+            })
+            .await
+            .inspect_err(fetch_error("l1"))?;
+
+        // Assemble the evm sketch to be sent to prover
+        let input = host_executor
+            .finalize()
+            .await
+            .inspect_err(fetch_error("l1"))?;
+        Ok(L1Sketch {
+            block_hash,
+            sketch: bincode::serialize(&input)?.into(),
+        })
+    }
+
+    async fn current_header_block(&self, root_chain: Address, block_number: u64) -> Result<u64> {
+        let response = self
+            .call(
+                root_chain,
+                RootChain::currentHeaderBlockCall {},
+                block_number,
+            )
+            .await?;
+        to_u64(response._0, "current header block")
+    }
+
+    async fn header_block(
+        &self,
+        root_chain: Address,
+        id: u64,
+        block_number: u64,
+    ) -> Result<HeaderBlock> {
+        let call = RootChain::headerBlocksCall { _0: U256::from(id) };
+        let response = self.call(root_chain, call, block_number).await?;
+        Ok(HeaderBlock {
+            root: response.root,
+            start: to_u64(response.start, "header block start")?,
+            end: to_u64(response.end, "header block end")?,
+            proposer: response.proposer,
+        })
+    }
+
+    async fn validator_set(
+        &self,
+        stake_manager: Address,
+        block_number: u64,
+    ) -> Result<ValidatorSet> {
+        let block_id = BlockId::from(BlockNumberOrTag::Number(block_number));
+        fetch_validator_set(&self.provider, stake_manager, block_id)
+            .await
+            .inspect_err(fetch_error("l1"))
+    }
+
+    async fn account_proof(
+        &self,
+        address: Address,
+        keys: Vec<B256>,
+        block_number: u64,
+    ) -> Result<AccountProof> {
+        let proof = self
+            .provider
+            .get_proof(address, keys.clone())
+            .block_id(BlockId::from(BlockNumberOrTag::Number(block_number)))
+            .await
+            .inspect_err(fetch_error("l1"))?;
+        Ok(AccountProof {
+            nonce: proof.nonce,
+            balance: proof.balance,
+            code_hash: proof.code_hash,
+            storage_hash: proof.storage_hash,
+            account_proof: proof.account_proof,
+            storage_proofs: keys
+                .into_iter()
+                .zip(proof.storage_proof)
+                .map(|(key, storage_proof)| StorageProof {
+                    key,
+                    value: storage_proof.value,
+                    proof: storage_proof.proof,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Bor source reading from an rpc node.
+pub struct RpcBorSource {
+    provider: ReqwestProvider,
+}
+
+impl RpcBorSource {
+    pub fn new(provider: ReqwestProvider) -> Self {
+        Self { provider }
+    }
+}
+
+async fn fetch_bor_header(provider: &ReqwestProvider, number: u64) -> Result<Header> {
+    let block = provider
+        .get_block_by_number(BlockNumberOrTag::Number(number), false)
+        .await?
+        .ok_or_else(|| eyre::eyre!("bor block {} not found", number))?;
+    Header::try_from(block.header)
+        .map_err(|err| eyre::eyre!("invalid header of bor block {}: {}", number, err))
+}
+
+#[async_trait]
+impl BorSource for RpcBorSource {
+    async fn headers(&self, start: u64, end: u64) -> Result<Vec<Header>> {
+        // Checkpoints span hundreds of blocks, so the headers are fetched a batch at a time
+        let numbers: Vec<u64> = (start..=end).collect();
+        let mut headers = Vec::with_capacity(numbers.len());
+        for batch in numbers.chunks(BOR_HEADER_BATCH_SIZE) {
+            let mut set = JoinSet::new();
+            for (index, number) in batch.iter().copied().enumerate() {
+                let provider = self.provider.clone();
+                set.spawn(async move { (index, fetch_bor_header(&provider, number).await) });
+            }
+            let mut fetched: Vec<Option<Header>> = vec![None; batch.len()];
+            while let Some(result) = set.join_next().await {
+                let (index, header) = result?;
+                fetched[index] = Some(header.inspect_err(fetch_error("bor"))?);
+            }
+            headers.extend(fetched.into_iter().flatten());
+        }
+        Ok(headers)
+    }
+}
+
+/// In-memory source of heimdall, L1 and bor data, used as a fake in tests and for replaying
+/// the data recorded by a `Recorder` (fixtures). L1 data is keyed by the block number only, so
+/// it should only hold the data of a single network.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemorySource {
+    #[serde(default)]
+    pub checkpoints: BTreeMap<u64, CheckpointResponse>,
+    /// Defaults to the highest checkpoint id.
+    pub checkpoint_count: Option<u64>,
+    #[serde(default)]
+    pub txs: BTreeMap<String, TxResponse>,
+    #[serde(default)]
+    pub heimdall_blocks: BTreeMap<u64, BlockResponse>,
+    #[serde(default)]
+    pub spans: BTreeMap<u64, SpanResponse>,
+    #[serde(default)]
+    pub milestones: BTreeMap<u64, MilestoneResponse>,
+    /// Defaults to the milestone with the highest number.
+    pub latest_milestone: Option<MilestoneResponse>,
+    #[serde(default)]
+    pub l1_sketches: BTreeMap<u64, L1Sketch>,
+    #[serde(default)]
+    pub current_header_blocks: BTreeMap<u64, u64>,
+    /// Header blocks by id (rather than L1 block) as they don't change once received.
+    #[serde(default)]
+    pub header_blocks: BTreeMap<u64, HeaderBlock>,
+    #[serde(default)]
+    pub validator_sets: BTreeMap<u64, ValidatorSet>,
+    /// Proofs of the stake manager (and it's slots) by L1 block. Only the requested slots are
+    /// returned so a single proof can hold the slots of all validators.
+    #[serde(default)]
+    pub account_proofs: BTreeMap<u64, AccountProof>,
+    #[serde(default)]
+    pub bor_headers: BTreeMap<u64, Header>,
+}
+
+impl MemorySource {
+    /// Loads a fixture saved using `save`.
+    pub fn load(path: &str) -> Result<Self> {
+        let file =
+            File::open(path).map_err(|err| eyre::eyre!("unable to open {}: {}", path, err))?;
+        serde_json::from_reader(BufReader::new(file))
+            .map_err(|err| eyre::eyre!("invalid fixture {}: {}", path, err))
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+fn missing<T>(what: String) -> Result<T> {
+    Err(eyre::eyre!("{} not found in the in-memory source", what))
+}
+
+fn get<K: Ord, V: Clone>(
+    map: &BTreeMap<K, V>,
+    key: &K,
+    what: impl FnOnce() -> String,
+) -> Result<V> {
+    match map.get(key) {
+        Some(value) => Ok(value.clone()),
+        None => missing(what()),
+    }
+}
+
+fn latest<V: Clone>(map: &BTreeMap<u64, V>, what: &str) -> Result<V> {
+    match map.last_key_value() {
+        Some((_, value)) => Ok(value.clone()),
+        None => missing(format!("latest {}", what)),
+    }
+}
+
+#[async_trait]
+impl HeimdallSource for MemorySource {
+    async fn fetch_checkpoint_by_id(&self, id: u64) -> Result<CheckpointResponse> {
+        get(&self.checkpoints, &id, || format!("checkpoint {}", id))
+    }
+
+    async fn fetch_checkpoint_count(&self) -> Result<u64> {
+        Ok(self
+            .checkpoint_count
+            .or_else(|| self.checkpoints.keys().last().copied())
+            .unwrap_or_default())
+    }
+
+    async fn fetch_tx_by_hash(&self, hash: String) -> Result<TxResponse> {
+        get(&self.txs, &hash, || format!("tx {}", hash))
+    }
+
+    async fn fetch_block_by_number(&self, number: u64) -> Result<BlockResponse> {
+        get(&self.heimdall_blocks, &number, || {
+            format!("heimdall block {}", number)
+        })
+    }
+
+    async fn fetch_span_by_id(&self, id: u64) -> Result<SpanResponse> {
+        get(&self.spans, &id, || format!("span {}", id))
+    }
+
+    async fn fetch_latest_span(&self) -> Result<SpanResponse> {
+        latest(&self.spans, "span")
+    }
+
+    async fn fetch_milestone_by_number(&self, number: u64) -> Result<MilestoneResponse> {
+        get(&self.milestones, &number, || {
+            format!("milestone {}", number)
+        })
+    }
+
+    async fn fetch_latest_milestone(&self) -> Result<MilestoneResponse> {
+        match self.latest_milestone.as_ref() {
+            Some(milestone) => Ok(milestone.clone()),
+            None => latest(&self.milestones, "milestone"),
+        }
+    }
+}
+
+#[async_trait]
+impl L1Source for MemorySource {
+    async fn root_chain_sketch(&self, _: Address, block_number: u64) -> Result<L1Sketch> {
+        get(&self.l1_sketches, &block_number, || {
+            format!("L1 sketch at block {}", block_number)
+        })
+    }
+
+    async fn current_header_block(&self, _: Address, block_number: u64) -> Result<u64> {
+        get(&self.current_header_blocks, &block_number, || {
+            format!("current header block at L1 block {}", block_number)
+        })
+    }
+
+    async fn header_block(&self, _: Address, id: u64, _: u64) -> Result<HeaderBlock> {
+        get(&self.header_blocks, &id, || format!("header block {}", id))
+    }
+
+    async fn validator_set(&self, _: Address, block_number: u64) -> Result<ValidatorSet> {
+        get(&self.validator_sets, &block_number, || {
+            format!("validator set at L1 block {}", block_number)
+        })
+    }
+
+    async fn account_proof(
+        &self,
+        _: Address,
+        keys: Vec<B256>,
+        block_number: u64,
+    ) -> Result<AccountProof> {
+        let mut proof = get(&self.account_proofs, &block_number, || {
+            format!("account proof at L1 block {}", block_number)
+        })?;
+        let storage_proofs = std::mem::take(&mut proof.storage_proofs);
+        for key in keys {
+            match storage_proofs
+                .iter()
+                .find(|storage_proof| storage_proof.key == key)
+            {
+                Some(storage_proof) => proof.storage_proofs.push(storage_proof.clone()),
+                None => return missing(format!("slot {} at L1 block {}", key, block_number)),
+            }
+        }
+        Ok(proof)
+    }
+}
+
+#[async_trait]
+impl BorSource for MemorySource {
+    async fn headers(&self, start: u64, end: u64) -> Result<Vec<Header>> {
+        (start..=end)
+            .map(|number| {
+                get(&self.bor_headers, &number, || {
+                    format!("bor block {}", number)
+                })
+            })
+            .collect()
+    }
+}
+
+/// Source which passes through to the given (live) sources and records all responses, so
+/// that they can be saved as a fixture and replayed using `MemorySource`.
+pub struct Recorder<'a> {
+    heimdall: &'a dyn HeimdallSource,
+    l1: &'a dyn L1Source,
+    bor: &'a dyn BorSource,
+    recorded: Mutex<MemorySource>,
+}
+
+impl<'a> Recorder<'a> {
+    pub fn new(
+        heimdall: &'a dyn HeimdallSource,
+        l1: &'a dyn L1Source,
+        bor: &'a dyn BorSource,
+    ) -> Self {
+        Self {
+            heimdall,
+            l1,
+            bor,
+            recorded: Mutex::new(MemorySource::default()),
+        }
+    }
+
+    pub fn recorded(&self) -> MemorySource {
+        self.recorded.lock().unwrap().clone()
+    }
+
+    fn record(&self, f: impl FnOnce(&mut MemorySource)) {
+        f(&mut self.recorded.lock().unwrap());
+    }
+}
+
+#[async_trait]
+impl HeimdallSource for Recorder<'_> {
+    async fn fetch_checkpoint_by_id(&self, id: u64) -> Result<CheckpointResponse> {
+        let response = self.heimdall.fetch_checkpoint_by_id(id).await?;
+        self.record(|r| {
+            r.checkpoints.insert(id, response.clone());
+        });
+        Ok(response)
+    }
+
+    async fn fetch_checkpoint_count(&self) -> Result<u64> {
+        let count = self.heimdall.fetch_checkpoint_count().await?;
+        self.record(|r| r.checkpoint_count = Some(count));
+        Ok(count)
+    }
+
+    async fn fetch_tx_by_hash(&self, hash: String) -> Result<TxResponse> {
+        let response = self.heimdall.fetch_tx_by_hash(hash.clone()).await?;
+        self.record(|r| {
+            r.txs.insert(hash, response.clone());
+        });
+        Ok(response)
+    }
+
+    async fn fetch_block_by_number(&self, number: u64) -> Result<BlockResponse> {
+        let response = self.heimdall.fetch_block_by_number(number).await?;
+        self.record(|r| {
+            r.heimdall_blocks.insert(number, response.clone());
+        });
+        Ok(response)
+    }
+
+    async fn fetch_span_by_id(&self, id: u64) -> Result<SpanResponse> {
+        let response = self.heimdall.fetch_span_by_id(id).await?;
+        self.record(|r| {
+            r.spans.insert(id, response.clone());
+        });
+        Ok(response)
+    }
+
+    async fn fetch_latest_span(&self) -> Result<SpanResponse> {
+        let response = self.heimdall.fetch_latest_span().await?;
+        self.record(|r| {
+            r.spans.insert(response.result.span_id, response.clone());
+        });
+        Ok(response)
+    }
+
+    async fn fetch_milestone_by_number(&self, number: u64) -> Result<MilestoneResponse> {
+        let response = self.heimdall.fetch_milestone_by_number(number).await?;
+        self.record(|r| {
+            r.milestones.insert(number, response.clone());
+        });
+        Ok(response)
+    }
+
+    async fn fetch_latest_milestone(&self) -> Result<MilestoneResponse> {
+        let response = self.heimdall.fetch_latest_milestone().await?;
+        self.record(|r| r.latest_milestone = Some(response.clone()));
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl L1Source for Recorder<'_> {
+    async fn root_chain_sketch(&self, root_chain: Address, block_number: u64) -> Result<L1Sketch> {
+        let sketch = self.l1.root_chain_sketch(root_chain, block_number).await?;
+        self.record(|r| {
+            r.l1_sketches.insert(block_number, sketch.clone());
+        });
+        Ok(sketch)
+    }
+
+    async fn current_header_block(&self, root_chain: Address, block_number: u64) -> Result<u64> {
+        let id = self
+            .l1
+            .current_header_block(root_chain, block_number)
+            .await?;
+        self.record(|r| {
+            r.current_header_blocks.insert(block_number, id);
+        });
+        Ok(id)
+    }
+
+    async fn header_block(
+        &self,
+        root_chain: Address,
+        id: u64,
+        block_number: u64,
+    ) -> Result<HeaderBlock> {
+        let header_block = self.l1.header_block(root_chain, id, block_number).await?;
+        self.record(|r| {
+            r.header_blocks.insert(id, header_block.clone());
+        });
+        Ok(header_block)
+    }
+
+    async fn validator_set(
+        &self,
+        stake_manager: Address,
+        block_number: u64,
+    ) -> Result<ValidatorSet> {
+        let validator_set = self.l1.validator_set(stake_manager, block_number).await?;
+        self.record(|r| {
+            r.validator_sets.insert(block_number, validator_set.clone());
+        });
+        Ok(validator_set)
+    }
+
+    async fn account_proof(
+        &self,
+        address: Address,
+        keys: Vec<B256>,
+        block_number: u64,
+    ) -> Result<AccountProof> {
+        let proof = self.l1.account_proof(address, keys, block_number).await?;
+        self.record(|r| {
+            r.account_proofs.insert(block_number, proof.clone());
+        });
+        Ok(proof)
+    }
+}
+
+#[async_trait]
+impl BorSource for Recorder<'_> {
+    async fn headers(&self, start: u64, end: u64) -> Result<Vec<Header>> {
+        let headers = self.bor.headers(start, end).await?;
+        self.record(|r| {
+            for header in headers.iter() {
+                r.bor_headers.insert(header.number, header.clone());
+            }
+        });
+        Ok(headers)
+    }
+}
@@ -0,0 +1,441 @@
+use crate::config::NetworkConfig;
+use crate::contract::{ContractClient, RevertError, TxConfig};
+use crate::metrics::metrics;
+use crate::signer::{default_private_key_env, SignerConfig};
+
+use alloy_primitives::{Address, B256, U256};
+use alloy_provider::{Provider, ReqwestProvider};
+use alloy_rpc_types::BlockNumberOrTag;
+use alloy_sol_types::{SolCall, SolType};
+use eyre::Result;
+use serde::Deserialize;
+use sp1_sdk::SP1ProofWithPublicValues;
+use std::{fs::File, io::BufReader, time::Duration};
+use tokio::task::JoinSet;
+use tracing::{info, instrument, warn, Instrument};
+use zk_checkpoint_lib::{
+    beacon::{BEACON_ROOTS_HISTORY_LENGTH, BLOCKHASH_HISTORY_LENGTH},
+    AggregateCommitStruct, CommitStruct, PoSVerifier,
+};
+
+/// Gas used by a checkpoint submission verifying the validator signatures on-chain (on mainnet,
+/// as cited in the README) which the proofs are compared against.
+pub const LEGACY_CHECKPOINT_GAS: u64 = 2_200_000;
+
+/// Chain on which the verifier is deployed and proofs are submitted to. The L1 block a proof is
+/// anchored to only exists on the L1 of the network, so all destinations have to be deployed on
+/// it (e.g. using different verifiers or signers).
+#[derive(Debug, Clone, Deserialize)]
+pub struct Destination {
+    pub name: String,
+    pub rpc_url: String,
+    pub chain_id: u64,
+    pub verifier: String,
+    /// Name of the env var holding the private key used for sending txs.
+    #[serde(default = "default_private_key_env")]
+    pub private_key_env: String,
+    /// Signer used for sending txs, overrides `private_key_env`.
+    #[serde(default)]
+    pub signer: Option<SignerConfig>,
+    /// Number of confirmations to wait for, overrides `TX_CONFIRMATIONS`.
+    #[serde(default)]
+    pub confirmations: Option<u64>,
+}
+
+impl Destination {
+    /// Destination configured by the selected network (i.e. it's verifier on L1).
+    pub fn from_config(config: &NetworkConfig) -> Result<Self> {
+        Ok(Self {
+            name: config.name.clone(),
+            rpc_url: config.eth_rpc_url()?.to_string(),
+            chain_id: config.l1_chain_id()?,
+            verifier: config.verifier()?.to_string(),
+            private_key_env: default_private_key_env(),
+            signer: Some(config.signer_config()),
+            confirmations: None,
+        })
+    }
+
+    pub fn client(&self) -> Result<ContractClient> {
+        let signer = match &self.signer {
+            Some(config) => config.signer()?,
+            None => SignerConfig::PrivateKey {
+                env: self.private_key_env.clone(),
+            }
+            .signer()?,
+        };
+        let client =
+            ContractClient::with_signer(self.chain_id, &self.rpc_url, signer, &self.verifier)?;
+        let mut tx_config = TxConfig::from_env()?;
+        if let Some(confirmations) = self.confirmations {
+            tx_config.confirmations = confirmations;
+        }
+        Ok(client.with_tx_config(tx_config))
+    }
+}
+
+/// Loads the destinations from the given json file (a list of destinations) or falls back to
+/// the verifier of the selected network. Destinations on another chain than the L1 of the
+/// network are rejected.
+pub fn load_destinations(path: Option<&str>, config: &NetworkConfig) -> Result<Vec<Destination>> {
+    match path {
+        Some(path) => {
+            let reader = BufReader::new(File::open(path)?);
+            let destinations: Vec<Destination> = serde_json::from_reader(reader)?;
+            if destinations.is_empty() {
+                eyre::bail!("no destinations configured in {}", path);
+            }
+            let l1_chain_id = config.l1_chain_id()?;
+            for destination in destinations.iter() {
+                if destination.chain_id != l1_chain_id {
+                    eyre::bail!(
+                        "destination {} is on chain {}, but proofs are anchored to L1 chain {}",
+                        destination.name,
+                        destination.chain_id,
+                        l1_chain_id
+                    );
+                }
+            }
+            Ok(destinations)
+        }
+        None => Ok(vec![Destination::from_config(config)?]),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SubmissionStatus {
+    Submitted { tx_hash: B256 },
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct Submission {
+    pub destination: String,
+    pub attempts: u32,
+    pub status: SubmissionStatus,
+}
+
+/// Builds the calldata for submitting the proof to the verifier along with the L1 block
+/// (number and hash) the proof is anchored to.
+pub fn build_calldata(
+    proof: &SP1ProofWithPublicValues,
+    aggregated: bool,
+) -> Result<(Vec<u8>, u64, B256)> {
+    let public_values = proof.public_values.to_vec();
+    if aggregated {
+        let commit = AggregateCommitStruct::abi_decode_params(&public_values, true)?;
+        let call_data = PoSVerifier::verifyAggregatedCheckpointsCall {
+            _proofBytes: proof.bytes().into(),
+            _l1BlockHash: commit.l1_block_hash,
+            _l1BlockNumber: commit.l1_block_number,
+            _prevBorBlockHash: commit.prev_bor_block_hash,
+            _prevBorBlockNumber: commit.prev_bor_block_number,
+            _borBlockHash: commit.bor_block_hash,
+            _borStartBlock: commit.bor_start_block,
+            _borBlockNumber: commit.bor_block_number,
+            _rootHashes: commit.root_hashes,
+            _validatorSetHash: commit.validator_set_hash,
+        }
+        .abi_encode();
+        Ok((call_data, commit.l1_block_number.to(), commit.l1_block_hash))
+    } else {
+        let commit = CommitStruct::abi_decode(&public_values, true)?;
+        let call_data = PoSVerifier::verifyCheckpointSignaturesCall {
+            _proofBytes: proof.bytes().into(),
+            _l1BlockHash: commit.l1_block_hash,
+            _l1BlockNumber: commit.l1_block_number,
+            _prevBorBlockHash: commit.prev_bor_block_hash,
+            _prevBorBlockNumber: commit.prev_bor_block_number,
+            _borBlockHash: commit.bor_block_hash,
+            _borStartBlock: commit.bor_start_block,
+            _borBlockNumber: commit.bor_block_number,
+            _rootHash: commit.root_hash,
+            _validatorSetHash: commit.validator_set_hash,
+        }
+        .abi_encode();
+        Ok((call_data, commit.l1_block_number.to(), commit.l1_block_hash))
+    }
+}
+
+/// Returns the (last) bor block number verified by the proof.
+pub fn bor_block_number(proof: &SP1ProofWithPublicValues, aggregated: bool) -> Result<u64> {
+    let public_values = proof.public_values.to_vec();
+    let bor_block_number = if aggregated {
+        AggregateCommitStruct::abi_decode_params(&public_values, true)?.bor_block_number
+    } else {
+        CommitStruct::abi_decode(&public_values, true)?.bor_block_number
+    };
+    Ok(bor_block_number.to())
+}
+
+/// Checks that the proof is for the expected network i.e. that the bor chain id and the stake
+/// manager (which the validator set is read from) committed by the program match the given
+/// ones. The verifier contract rejects such a proof too, but only once it's submitted.
+pub fn check_network(
+    proof: &SP1ProofWithPublicValues,
+    aggregated: bool,
+    bor_chain_id: u64,
+    stake_manager: Address,
+) -> Result<()> {
+    let public_values = proof.public_values.to_vec();
+    let (committed_chain_id, committed_stake_manager) = if aggregated {
+        let commit = AggregateCommitStruct::abi_decode_params(&public_values, true)?;
+        (commit.bor_chain_id, commit.stake_manager)
+    } else {
+        let commit = CommitStruct::abi_decode(&public_values, true)?;
+        (commit.bor_chain_id, commit.stake_manager)
+    };
+    if committed_chain_id != U256::from(bor_chain_id) {
+        eyre::bail!(
+            "proof is for bor chain {}, expected: {}",
+            committed_chain_id,
+            bor_chain_id
+        );
+    }
+    if committed_stake_manager != stake_manager {
+        eyre::bail!(
+            "proof is against stake manager {}, expected: {}",
+            committed_stake_manager,
+            stake_manager
+        );
+    }
+
+    Ok(())
+}
+
+/// Checks if the L1 block the proof is anchored to is part of the canonical chain and recent
+/// enough to be checked on-chain either via `blockhash` or the EIP-4788 beacon roots.
+pub async fn check_l1_anchor(
+    provider: &ReqwestProvider,
+    l1_block_number: u64,
+    l1_block_hash: B256,
+) -> Result<()> {
+    let block_hash = provider
+        .get_block_by_number(BlockNumberOrTag::Number(l1_block_number), false)
+        .await?
+        .and_then(|block| block.header.hash.into());
+    if block_hash != Some(l1_block_hash) {
+        eyre::bail!(
+            "l1 block hash mismatch for block {}, expected: {}, got: {:?}",
+            l1_block_number,
+            l1_block_hash,
+            block_hash
+        );
+    }
+
+    let latest = provider.get_block_number().await?;
+    let age = latest.saturating_sub(l1_block_number);
+    if age <= BLOCKHASH_HISTORY_LENGTH {
+        info!(l1_block_number, "L1 block can be anchored using blockhash");
+    } else if age <= BEACON_ROOTS_HISTORY_LENGTH {
+        info!(
+            l1_block_number,
+            age, "L1 block can only be anchored using the beacon root"
+        );
+    } else {
+        eyre::bail!(
+            "L1 block {} is {} blocks old and can't be anchored on-chain",
+            l1_block_number,
+            age
+        );
+    }
+
+    Ok(())
+}
+
+/// Submits the calldata to a single destination, retrying failures up to `retries` times.
+/// Reverts aren't retried as the proof would be rejected again. The L1 anchor is checked
+/// against the rpc of the destination first, which fails if it's on another chain.
+#[instrument(skip_all, fields(destination = %destination.name))]
+pub async fn submit(
+    destination: &Destination,
+    calldata: Vec<u8>,
+    l1_block_number: u64,
+    l1_block_hash: B256,
+    retries: u32,
+) -> Submission {
+    let failed = |attempts, error| Submission {
+        destination: destination.name.clone(),
+        attempts,
+        status: SubmissionStatus::Failed { error },
+    };
+    // Reuse the client across attempts so that a retry replaces the tx of the failed attempt
+    let client = match destination.client() {
+        Ok(client) => client,
+        Err(err) => return failed(0, err.to_string()),
+    };
+    if let Err(err) = check_l1_anchor(client.provider(), l1_block_number, l1_block_hash).await {
+        return failed(0, err.to_string());
+    }
+
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let err = match client.send(calldata.clone()).await {
+            Ok(receipt) => {
+                metrics()
+                    .submission_gas_used
+                    .set_with(&destination.name, receipt.gas_used as f64);
+                return Submission {
+                    destination: destination.name.clone(),
+                    attempts,
+                    status: SubmissionStatus::Submitted {
+                        tx_hash: receipt.transaction_hash,
+                    },
+                };
+            }
+            Err(err) => err,
+        };
+
+        warn!(attempts, %err, "Submission attempt failed");
+        if err.downcast_ref::<RevertError>().is_some() || attempts > retries {
+            return failed(attempts, err.to_string());
+        }
+        tokio::time::sleep(Duration::from_secs(2u64.pow(attempts))).await;
+    }
+}
+
+/// Submits the same calldata to all destinations in parallel. Each destination is retried
+/// independently so a failing chain doesn't hold back the others.
+pub async fn submit_to_all(
+    destinations: &[Destination],
+    calldata: Vec<u8>,
+    l1_block_number: u64,
+    l1_block_hash: B256,
+    retries: u32,
+) -> Vec<Submission> {
+    let mut set = JoinSet::new();
+    for (index, destination) in destinations.iter().enumerate() {
+        let destination = destination.clone();
+        let calldata = calldata.clone();
+        set.spawn(
+            async move {
+                let submission = submit(
+                    &destination,
+                    calldata,
+                    l1_block_number,
+                    l1_block_hash,
+                    retries,
+                )
+                .await;
+                (index, submission)
+            }
+            .in_current_span(),
+        );
+    }
+
+    let mut submissions: Vec<Option<Submission>> = vec![None; destinations.len()];
+    while let Some(result) = set.join_next().await {
+        match result {
+            Ok((index, submission)) => submissions[index] = Some(submission),
+            Err(err) => warn!(%err, "Submission task failed"),
+        }
+    }
+
+    submissions
+        .into_iter()
+        .zip(destinations.iter())
+        .map(|(submission, destination)| {
+            submission.unwrap_or_else(|| Submission {
+                destination: destination.name.clone(),
+                attempts: 0,
+                status: SubmissionStatus::Failed {
+                    error: "submission task failed".to_string(),
+                },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::tests::{FakeChain, FakeRpc};
+
+    const PRIVATE_KEY_ENV: &str = "SUBMIT_TEST_PRIVATE_KEY";
+    const L1_BLOCK_HASH: B256 = B256::repeat_byte(1);
+
+    fn destination(name: &str, rpc: &FakeRpc) -> Destination {
+        std::env::set_var(
+            PRIVATE_KEY_ENV,
+            "0x0123456789012345678901234567890123456789012345678901234567890123",
+        );
+        Destination {
+            name: name.to_string(),
+            rpc_url: rpc.url.clone(),
+            chain_id: 1,
+            verifier: Address::with_last_byte(10).to_string(),
+            private_key_env: PRIVATE_KEY_ENV.to_string(),
+            signer: None,
+            confirmations: Some(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submit_to_all_retries_independently() {
+        let down = FakeRpc::start(FakeChain {
+            send_error: Some("node is down".to_string()),
+            block_hash: L1_BLOCK_HASH,
+            ..Default::default()
+        })
+        .await;
+        let up = FakeRpc::start(FakeChain {
+            block_hash: L1_BLOCK_HASH,
+            ..Default::default()
+        })
+        .await;
+        let destinations = [destination("down", &down), destination("up", &up)];
+
+        let submissions = submit_to_all(&destinations, vec![1], 1, L1_BLOCK_HASH, 1).await;
+        assert_eq!(submissions.len(), 2);
+
+        assert_eq!(submissions[0].destination, "down");
+        assert_eq!(submissions[0].attempts, 2);
+        match &submissions[0].status {
+            SubmissionStatus::Failed { error } => {
+                assert!(error.contains("node is down"), "{}", error)
+            }
+            status => panic!("unexpected status: {:?}", status),
+        }
+
+        assert_eq!(submissions[1].destination, "up");
+        assert_eq!(submissions[1].attempts, 1);
+        match &submissions[1].status {
+            SubmissionStatus::Submitted { tx_hash } => assert_eq!(*tx_hash, up.sent()[0].0),
+            status => panic!("unexpected status: {:?}", status),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submit_revert_not_retried() {
+        let rpc = FakeRpc::start(FakeChain {
+            revert: Some("previous checkpoint mismatch".to_string()),
+            block_hash: L1_BLOCK_HASH,
+            ..Default::default()
+        })
+        .await;
+        let submission = submit(&destination("reverts", &rpc), vec![1], 1, L1_BLOCK_HASH, 3).await;
+        assert_eq!(submission.attempts, 1);
+        assert!(matches!(submission.status, SubmissionStatus::Failed { .. }));
+        assert!(rpc.sent().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_submit_checks_anchor_on_destination() {
+        // E.g. a destination on another chain than the L1 the proof is anchored to
+        let rpc = FakeRpc::start(FakeChain {
+            block_hash: B256::repeat_byte(2),
+            ..Default::default()
+        })
+        .await;
+        let submission = submit(&destination("other", &rpc), vec![1], 1, L1_BLOCK_HASH, 1).await;
+        assert_eq!(submission.attempts, 0);
+        match &submission.status {
+            SubmissionStatus::Failed { error } => {
+                assert!(error.starts_with("l1 block hash mismatch"), "{}", error)
+            }
+            status => panic!("unexpected status: {:?}", status),
+        }
+        assert!(rpc.sent().is_empty());
+    }
+}
@@ -1,62 +1,291 @@
-use serde::Deserialize;
+use alloy_primitives::{hex, Address, B256};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use checkpoint_proof::types::{deserialize_checkpoint_tx, heimdall_types::CheckpointMsg};
+use eyre::Result;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
 
-#[derive(Debug, Deserialize)]
+/// Bytes which heimdall and tendermint encode as base64 (i.e. txs, side tx hashes and sigs).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Base64Bytes(pub Vec<u8>);
+
+impl fmt::Display for Base64Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&BASE64_STANDARD.encode(&self.0))
+    }
+}
+
+impl Serialize for Base64Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Base64Bytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        BASE64_STANDARD
+            .decode(&value)
+            .map(Base64Bytes)
+            .map_err(|err| de::Error::custom(format!("invalid base64 {:?}: {}", value, err)))
+    }
+}
+
+/// (De)serializes a `u64` which heimdall encodes as a decimal string (e.g. heights and chain
+/// ids). Plain numbers are accepted as well.
+pub(crate) mod u64_string {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(u64),
+    }
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        match StringOrNumber::deserialize(deserializer)? {
+            StringOrNumber::String(value) => value
+                .parse()
+                .map_err(|err| de::Error::custom(format!("invalid number {:?}: {}", value, err))),
+            StringOrNumber::Number(value) => Ok(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheckpointResponse {
     pub result: Checkpoint,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Checkpoint {
-    pub proposer: String,
+    pub proposer: Address,
     pub start_block: u64,
     pub end_block: u64,
-    pub root_hash: String,
-    pub bor_chain_id: String,
+    pub root_hash: B256,
+    #[serde(with = "u64_string")]
+    pub bor_chain_id: u64,
     pub timestamp: u64,
 }
 
-#[derive(Debug, Deserialize)]
+impl Checkpoint {
+    /// Checks that the checkpoint matches the message of the tx which proposed it, field by
+    /// field, as the program only commits to the message.
+    pub fn check_msg(&self, msg: &CheckpointMsg) -> Result<()> {
+        let mut mismatches = Vec::new();
+        if msg.proposer != self.proposer.as_slice() {
+            mismatches.push(format!(
+                "proposer ({} != {})",
+                self.proposer,
+                hex::encode_prefixed(&msg.proposer)
+            ));
+        }
+        if msg.start_block != self.start_block {
+            mismatches.push(format!(
+                "start block ({} != {})",
+                self.start_block, msg.start_block
+            ));
+        }
+        if msg.end_block != self.end_block {
+            mismatches.push(format!(
+                "end block ({} != {})",
+                self.end_block, msg.end_block
+            ));
+        }
+        if msg.root_hash != self.root_hash.as_slice() {
+            mismatches.push(format!(
+                "root hash ({} != {})",
+                self.root_hash,
+                hex::encode_prefixed(&msg.root_hash)
+            ));
+        }
+        if msg.bor_chain_id != self.bor_chain_id.to_string() {
+            mismatches.push(format!(
+                "bor chain id ({} != {})",
+                self.bor_chain_id, msg.bor_chain_id
+            ));
+        }
+        if !mismatches.is_empty() {
+            eyre::bail!(
+                "checkpoint doesn't match the checkpoint tx: {}",
+                mismatches.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TxResponse {
     pub result: TxResponseResult,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TxResponseResult {
-    pub hash: String,
-    pub height: String,
-    pub tx: String,
+    pub hash: B256,
+    #[serde(with = "u64_string")]
+    pub height: u64,
+    pub tx: Base64Bytes,
+}
+
+impl TxResponseResult {
+    /// Decodes the checkpoint message from the (amino encoded) tx.
+    pub fn checkpoint_msg(&self) -> Result<CheckpointMsg> {
+        let tx = deserialize_checkpoint_tx(&mut self.tx.0.clone())
+            .map_err(|err| eyre::eyre!("invalid checkpoint tx {}: {}", self.hash, err))?;
+        tx.msg
+            .ok_or_else(|| eyre::eyre!("checkpoint tx {} has no message", self.hash))
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockResponse {
     pub result: BlockResponseResult,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockResponseResult {
     pub block: Block,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
     pub last_commit: LastCommit,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LastCommit {
     pub precommits: Vec<Precommit>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Precommit {
-    pub validator_address: String,
+    pub validator_address: Address,
     pub side_tx_results: Option<Vec<SideTxResult>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SideTxResult {
-    #[serde(rename = "tx_hash")]
-    pub tx_hash: String,
+    pub tx_hash: Base64Bytes,
     pub result: i32,
-    pub sig: Option<String>,
-}
\ No newline at end of file
+    pub sig: Option<Base64Bytes>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpanResponse {
+    pub result: Span,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Span {
+    pub span_id: u64,
+    pub start_block: u64,
+    pub end_block: u64,
+    pub selected_producers: Vec<SpanValidator>,
+    #[serde(with = "u64_string")]
+    pub bor_chain_id: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpanValidator {
+    #[serde(rename = "ID")]
+    pub id: u64,
+    pub power: u64,
+    pub signer: Address,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointCountResponse {
+    pub result: CheckpointCount,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointCount {
+    pub result: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MilestoneResponse {
+    pub result: Milestone,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Milestone {
+    pub proposer: Address,
+    pub start_block: u64,
+    pub end_block: u64,
+    pub hash: B256,
+    #[serde(with = "u64_string")]
+    pub bor_chain_id: u64,
+    pub milestone_id: String,
+    pub timestamp: u64,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Amino encoded checkpoint tx (on amoy) proposing bor blocks 13383283 to 13383794.
+    pub(crate) const CHECKPOINT_TX: &str = "uAHwYl3uCm/XqKSpChRtwt1U8kl57CYhJ5THGv7+1yIoDBDz7LAGGPLwsAYiIG13yje6CCcTwisX8k0naX249I92JIpsCbcU/f5Pnp+xKiBLa5lLmdJONehiavQZoIfseEmNl2Jl5YedfCK5JBw7mDIFODAwMDISQX5H4v7pEORvrXwVu2+pyUKQJXkvyP8pVb5a7V3KDStwW6AwgsQnh/MKlPe+y/YEKxbVH8J6XqILlTOmiQhnSi8A";
+    pub(crate) const CHECKPOINT_TX_HASH: &str =
+        "ACDD21B4643C560BABEFD187E32EC0846C51475CD88351D67E2F267D79204C5C";
+
+    fn tx_json() -> String {
+        format!(
+            r#"{{"hash": "{}", "height": "42", "tx": "{}"}}"#,
+            CHECKPOINT_TX_HASH, CHECKPOINT_TX
+        )
+    }
+
+    const CHECKPOINT: &str = r#"{
+        "proposer": "0x6dc2dd54f24979ec26212794c71afefed722280c",
+        "start_block": 13383283,
+        "end_block": 13383794,
+        "root_hash": "0x6d77ca37ba082713c22b17f24d27697db8f48f76248a6c09b714fdfe4f9e9fb1",
+        "bor_chain_id": "80002",
+        "timestamp": 1730000000
+    }"#;
+
+    #[test]
+    fn test_decode_responses() {
+        let tx: TxResponseResult = serde_json::from_str(&tx_json()).unwrap();
+        assert_eq!(tx.height, 42);
+        assert_eq!(hex::encode_upper(tx.hash), CHECKPOINT_TX_HASH);
+        assert_eq!(tx.tx.0.len(), 186);
+
+        let checkpoint: Checkpoint = serde_json::from_str(CHECKPOINT).unwrap();
+        assert_eq!(checkpoint.bor_chain_id, 80002);
+        checkpoint.check_msg(&tx.checkpoint_msg().unwrap()).unwrap();
+
+        // Round trips as is, which fixtures rely on
+        let json = serde_json::to_string(&tx).unwrap();
+        let decoded: TxResponseResult = serde_json::from_str(&json).unwrap();
+        assert_eq!((decoded.hash, decoded.height), (tx.hash, tx.height));
+        assert_eq!(decoded.tx, tx.tx);
+
+        let err = serde_json::from_str::<TxResponseResult>(&tx_json().replace("\"42\"", "\"4x\""))
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid number \"4x\""));
+    }
+
+    #[test]
+    fn test_check_msg() {
+        let tx: TxResponseResult = serde_json::from_str(&tx_json()).unwrap();
+        let mut checkpoint: Checkpoint = serde_json::from_str(CHECKPOINT).unwrap();
+        checkpoint.end_block += 1;
+        checkpoint.bor_chain_id = 137;
+
+        let err = checkpoint
+            .check_msg(&tx.checkpoint_msg().unwrap())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "checkpoint doesn't match the checkpoint tx: end block (13383795 != 13383794), bor chain id (137 != 80002)"
+        );
+    }
+}
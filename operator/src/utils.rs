@@ -1,93 +1,106 @@
-use std::env;
-use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
-use reqwest::Client;
+use async_trait::async_trait;
 use eyre::Result;
+use tracing::debug;
 
-use crate::types::{TxResponse, CheckpointResponse, BlockResponse};
+use crate::heimdall::HeimdallSource;
+use crate::http::{HttpClient, HttpConfig};
+use crate::types::{
+    BlockResponse, CheckpointCountResponse, CheckpointResponse, MilestoneResponse, SpanResponse,
+    TxResponse,
+};
 
+/// Client for the heimdall rest api and the tendermint rpc. Each of them can have multiple
+/// endpoints which are tried in order. Created from the network config (see
+/// `NetworkConfig::pos_client`).
 pub struct PoSClient {
-    heimdall_url: String,
-    tendermint_url: String,
-    http_client: Client,
-    headers: HeaderMap,
+    heimdall_urls: Vec<String>,
+    tendermint_urls: Vec<String>,
+    http_client: HttpClient,
 }
 
-impl Default for PoSClient {
-    fn default() -> Self {
-        let heimdall_url =
-            env::var("HEIMDALL_REST_ENDPOINT").expect("HEIMDALL_REST_ENDPOINT not set");
-        let tendermint_url = env::var("TENDERMINT_ENDPOINT").expect("TENDERMINT_ENDPOINT not set");
-        let http_client = Client::new();
-
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            USER_AGENT,
-            HeaderValue::from_static("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/129.0.0.0 Safari/537.36"),
-        );
+/// Splits a comma separated list of endpoints (e.g. `https://a,https://b`).
+pub fn split_endpoints(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+        .collect()
+}
 
-        Self {
-            heimdall_url,
-            tendermint_url,
-            http_client,
-            headers,
-        }
+impl PoSClient {
+    pub fn new(
+        heimdall_urls: Vec<String>,
+        tendermint_urls: Vec<String>,
+        http_config: HttpConfig,
+    ) -> Result<Self> {
+        // Endpoints are joined with the paths as is, so drop any trailing slash
+        let trim = |urls: Vec<String>| -> Vec<String> {
+            urls.iter()
+                .map(|url| url.trim_end_matches('/').to_string())
+                .collect()
+        };
+        Ok(Self {
+            heimdall_urls: trim(heimdall_urls),
+            tendermint_urls: trim(tendermint_urls),
+            http_client: HttpClient::new("heimdall", http_config)?,
+        })
     }
 }
 
-impl PoSClient {
-    pub fn new(heimdall_url: String, tendermint_url: String) -> Self {
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            USER_AGENT,
-            HeaderValue::from_static("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/129.0.0.0 Safari/537.36"),
-        );
-        Self {
-            heimdall_url,
-            tendermint_url,
-            http_client: Client::new(),
-            headers,
-        }
+#[async_trait]
+impl HeimdallSource for PoSClient {
+    async fn fetch_checkpoint_by_id(&self, id: u64) -> Result<CheckpointResponse> {
+        let path = format!("/checkpoints/{}", id);
+        debug!(id, "Fetching checkpoint");
+        self.http_client.get_json(&self.heimdall_urls, &path).await
+    }
+
+    async fn fetch_checkpoint_count(&self) -> Result<u64> {
+        let path = "/checkpoints/count";
+        debug!("Fetching checkpoint count");
+        let response: CheckpointCountResponse =
+            self.http_client.get_json(&self.heimdall_urls, path).await?;
+        Ok(response.result.result)
+    }
+
+    async fn fetch_tx_by_hash(&self, hash: String) -> Result<TxResponse> {
+        let path = format!("/tx?hash={}", hash);
+        debug!(%hash, "Fetching checkpoint tx");
+        self.http_client
+            .get_json(&self.tendermint_urls, &path)
+            .await
+    }
+
+    async fn fetch_block_by_number(&self, number: u64) -> Result<BlockResponse> {
+        let path = format!("/block?height={}", number);
+        debug!(number, "Fetching heimdall block");
+        self.http_client
+            .get_json(&self.tendermint_urls, &path)
+            .await
+    }
+
+    async fn fetch_span_by_id(&self, id: u64) -> Result<SpanResponse> {
+        let path = format!("/bor/span/{}", id);
+        debug!(id, "Fetching span");
+        self.http_client.get_json(&self.heimdall_urls, &path).await
     }
 
-    pub async fn fetch_checkpoint_by_id(&self, id: u64) -> Result<CheckpointResponse> {
-        let url = format!("{}/checkpoints/{}", self.heimdall_url, id);
-        println!("Fetching checkpoint from: {}", url);
-        let response = self
-            .http_client
-            .get(url)
-            .headers(self.headers.clone())
-            .send()
-            .await?
-            .json::<CheckpointResponse>()
-            .await?;
-        Ok(response)
+    async fn fetch_latest_span(&self) -> Result<SpanResponse> {
+        let path = "/bor/latest-span";
+        debug!("Fetching latest span");
+        self.http_client.get_json(&self.heimdall_urls, path).await
     }
 
-    pub async fn fetch_tx_by_hash(&self, hash: String) -> Result<TxResponse> {
-        let url = format!("{}/tx?hash={}", self.tendermint_url, hash);
-        println!("Fetching checkpoint tx by hash: {}", url);
-        let response: TxResponse = self
-            .http_client
-            .get(url)
-            .headers(self.headers.clone())
-            .send()
-            .await?
-            .json::<TxResponse>()
-            .await?;
-        Ok(response)
+    async fn fetch_milestone_by_number(&self, number: u64) -> Result<MilestoneResponse> {
+        let path = format!("/milestone/{}", number);
+        debug!(number, "Fetching milestone");
+        self.http_client.get_json(&self.heimdall_urls, &path).await
     }
 
-    pub async fn fetch_block_by_number(&self, number: u64) -> Result<BlockResponse> {
-        let url = format!("{}/block?height={}", self.tendermint_url, number);
-        println!("Fetching heimdall block by number: {}", url);
-        let response = self
-            .http_client
-            .get(url)
-            .headers(self.headers.clone())
-            .send()
-            .await?
-            .json::<BlockResponse>()
-            .await?;
-        Ok(response)
+    async fn fetch_latest_milestone(&self) -> Result<MilestoneResponse> {
+        let path = "/milestone/latest";
+        debug!("Fetching latest milestone");
+        self.http_client.get_json(&self.heimdall_urls, path).await
     }
 }
@@ -0,0 +1,71 @@
+use alloy_primitives::{Address, U256};
+use alloy_provider::{Provider, ReqwestProvider};
+use alloy_rpc_types::{BlockId, TransactionInput, TransactionRequest};
+use alloy_sol_types::SolCall;
+use eyre::Result;
+use zk_checkpoint_lib::{
+    stake_manager::is_active_validator,
+    validator_set::{Validator, ValidatorSet},
+    StakeManager,
+};
+
+/// Executes a read only call on the contract at the given L1 block.
+pub async fn call<C: SolCall>(
+    provider: &ReqwestProvider,
+    contract: Address,
+    call: C,
+    block_id: BlockId,
+) -> Result<C::Return> {
+    let tx = TransactionRequest::default()
+        .to(contract)
+        .input(TransactionInput::new(call.abi_encode().into()));
+    let output = provider.call(&tx).block(block_id).await?;
+    Ok(C::abi_decode_returns(&output, true)?)
+}
+
+/// Fetches the active validator set from the stake manager at the given L1 block. This is
+/// done outside the zkVM and only used to decide which validators are to be proven.
+pub async fn fetch_validator_set(
+    provider: &ReqwestProvider,
+    stake_manager: Address,
+    block_id: BlockId,
+) -> Result<ValidatorSet> {
+    let nft_counter: u64 = call(
+        provider,
+        stake_manager,
+        StakeManager::NFTCounterCall {},
+        block_id,
+    )
+    .await?
+    ._0
+    .try_into()?;
+    let current_epoch = call(
+        provider,
+        stake_manager,
+        StakeManager::currentEpochCall {},
+        block_id,
+    )
+    .await?
+    ._0;
+
+    // Validator ids start from 1 and the NFT counter is assigned to the next validator
+    let mut validator_set = ValidatorSet::default();
+    for id in 1..nft_counter {
+        let validator = call(
+            provider,
+            stake_manager,
+            StakeManager::validatorsCall { _0: U256::from(id) },
+            block_id,
+        )
+        .await?;
+        if is_active_validator(validator._7, validator._3, current_epoch) {
+            validator_set.upsert(Validator {
+                id,
+                signer: validator._5,
+                stake: validator._0 + validator._11,
+            });
+        }
+    }
+
+    Ok(validator_set)
+}
@@ -1,35 +1,60 @@
 #![no_main]
 sp1_zkvm::entrypoint!(main);
 
-use alloy_primitives::{Address, Uint, B256};
+use alloy_primitives::{Address, Bytes, Uint, B256};
 use alloy_sol_types::SolType;
 use checkpoint_proof::checkpoint::{prove, CheckpointProofInput, CommitStruct};
+use zk_checkpoint_lib::stake_manager::AccountProof;
 
 pub fn main() {
     let tx_data = sp1_zkvm::io::read::<String>();
     let tx_hash = sp1_zkvm::io::read::<B256>();
     let sigs = sp1_zkvm::io::read::<Vec<String>>();
     let signers = sp1_zkvm::io::read::<Vec<Address>>();
+    let validator_ids = sp1_zkvm::io::read::<Vec<u64>>();
     let state_sketch_bytes = sp1_zkvm::io::read::<Vec<u8>>();
-    let root_chain_info_address = sp1_zkvm::io::read::<Address>();
+    let root_chain_address = sp1_zkvm::io::read::<Address>();
+    let stake_manager_address = sp1_zkvm::io::read::<Address>();
+    let stake_manager_proof = sp1_zkvm::io::read::<AccountProof>();
     let l1_block_hash = sp1_zkvm::io::read::<B256>();
-    let bor_block_hash = sp1_zkvm::io::read::<B256>();
+    let bor_start_header = sp1_zkvm::io::read::<Bytes>();
+    let bor_start_branch = sp1_zkvm::io::read::<Vec<B256>>();
+    let bor_end_header = sp1_zkvm::io::read::<Bytes>();
+    let bor_end_branch = sp1_zkvm::io::read::<Vec<B256>>();
+    let bor_chain_id = sp1_zkvm::io::read::<u64>();
     let input = CheckpointProofInput {
         tx_data,
         tx_hash,
         sigs,
         signers,
+        validator_ids,
         state_sketch_bytes,
-        root_chain_info_address,
+        root_chain_address,
+        stake_manager_address,
+        stake_manager_proof,
         l1_block_hash,
-        bor_block_hash,
+        bor_start_header,
+        bor_start_branch,
+        bor_end_header,
+        bor_end_branch,
+        bor_chain_id,
     };
     let commit = prove(input);
 
-    let bytes = CommitStruct::abi_encode_packed(&CommitStruct {
+    // Encoded as words (instead of packed) so that the stake manager address is padded the
+    // same way as by `abi.encode` in the verifier contract
+    let bytes = CommitStruct::abi_encode(&CommitStruct {
         l1_block_hash: commit.l1_block_hash,
+        l1_block_number: Uint::from(commit.l1_block_number),
+        prev_bor_block_hash: commit.prev_bor_block_hash,
+        prev_bor_block_number: Uint::from(commit.prev_bor_block_number),
         bor_block_hash: commit.bor_block_hash,
+        bor_start_block: Uint::from(commit.bor_start_block),
         bor_block_number: Uint::from(commit.bor_block_number),
+        root_hash: commit.root_hash,
+        validator_set_hash: commit.validator_set_hash,
+        bor_chain_id: Uint::from(commit.bor_chain_id),
+        stake_manager: commit.stake_manager,
     });
     sp1_zkvm::io::commit_slice(&bytes);
 }